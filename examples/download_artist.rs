@@ -0,0 +1,43 @@
+#![allow(clippy::unwrap_used)]
+
+const DIR: &str = "music";
+
+use qobuz::{
+    auth::Credentials,
+    downloader::Downloader,
+    link::{parse_url, ItemKind},
+    quality::Quality,
+    Client,
+};
+
+/// Download the discography of the artist whose ID or URL is given as the first argument,
+/// defaulting to the Beatles.
+#[tokio::main]
+async fn main() {
+    let artist_id = match std::env::args().nth(1) {
+        Some(arg) => match parse_url(&arg) {
+            Ok((ItemKind::Artist, id)) => id,
+            Ok((kind, _)) => panic!("Expected an artist URL, got a {kind:?} URL"),
+            Err(_) => arg,
+        },
+        None => "26390".to_string(),
+    };
+    let client = Client::new(Credentials::from_env().unwrap()).await.unwrap();
+    let artist = client.get_artist(&artist_id).await.unwrap();
+
+    println!("= {artist} =");
+    for album in &artist.albums {
+        println!("{album}");
+    }
+
+    let downloader = Downloader::new(client, DIR);
+    downloader
+        .download_and_tag_artist_with(&artist, Quality::Cd, false, |progress| {
+            println!(
+                "{}/{} albums downloaded",
+                progress.downloaded_albums, progress.total_albums
+            );
+        })
+        .await
+        .unwrap();
+}
@@ -2,59 +2,111 @@
 
 const DIR: &str = "music";
 
-use qobuz::downloader::Downloader;
-use qobuz::types::extra::WithExtra;
-use std::path::Path;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-
-use futures::stream;
-use futures::StreamExt;
+use qobuz::downloader::{m3u::PathStyle, Downloader};
+use qobuz::types::extra::WithoutExtra;
+use qobuz::types::Album;
+use std::path::PathBuf;
+
+use qobuz::quality::Quality;
 use qobuz::{auth::Credentials, Client};
-use qobuz::{quality::Quality, types::Track};
-use std::io::Write;
 
+struct Options {
+    kind: String,
+    quality: Quality,
+    out: PathBuf,
+    force: bool,
+    m3u_style: PathStyle,
+}
+
+impl Options {
+    /// Parse the options from the command line arguments:
+    /// `[tracks|albums|playlists] [--quality <quality>] [--out <dir>] [--no-overwrite]
+    /// [--absolute-m3u]`.
+    fn from_args() -> Self {
+        let mut options = Self {
+            kind: "tracks".to_string(),
+            quality: Quality::Cd,
+            out: PathBuf::from(DIR),
+            force: true,
+            m3u_style: PathStyle::Relative,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--quality" => options.quality = args.next().unwrap().parse().unwrap(),
+                "--out" => options.out = PathBuf::from(args.next().unwrap()),
+                "--no-overwrite" => options.force = false,
+                "--absolute-m3u" => options.m3u_style = PathStyle::Absolute,
+                _ => options.kind = arg,
+            }
+        }
+        options
+    }
+}
+
+/// Download the user's favorites. See `Options::from_args` for the accepted arguments.
 #[tokio::main]
 async fn main() {
+    let options = Options::from_args();
     let client = Client::new(Credentials::from_env().unwrap()).await.unwrap();
-    let tracks: Vec<_> = client
-        .get_user_favorites::<Track<WithExtra>>()
+    let downloader = Downloader::new(client.clone(), &options.out);
+
+    match options.kind.as_str() {
+        "tracks" => download_tracks(&downloader, &options).await,
+        "albums" => download_albums(&client, &downloader, &options).await,
+        "playlists" => download_playlists(&client, &downloader, &options).await,
+        kind => panic!("Unknown kind of favorites `{kind}`"),
+    }
+}
+
+async fn download_tracks(downloader: &Downloader, options: &Options) {
+    downloader
+        .download_favorite_tracks(
+            options.quality.clone(),
+            options.force,
+            4,
+            options.m3u_style,
+            |progress| {
+                if progress.item_progress.is_none() {
+                    println!("{}/{}", progress.completed_items, progress.total_items);
+                }
+            },
+        )
+        .await
+        .unwrap();
+}
+
+async fn download_albums(client: &Client, downloader: &Downloader, options: &Options) {
+    let albums: Vec<_> = client
+        .get_user_favorites::<Album<WithoutExtra>>()
         .await
         .unwrap()
         .into_iter()
-        .filter(|t| t.streamable)
+        .filter(|a| a.streamable)
         .collect();
+    let n = albums.len();
+    for (i, album) in albums.into_iter().enumerate() {
+        println!("{}/{}: {}", i + 1, n, album);
+        let album = client.get_album(&album.id).await.unwrap();
+        downloader
+            .download_and_tag_album(&album, options.quality.clone(), options.force)
+            .await
+            .unwrap();
+    }
+}
 
-    let downloader = Downloader::new(client.clone(), Path::new(DIR));
-
-    let n = tracks.len();
-    let v = vec![None; n];
-    let playlist: Arc<RwLock<Vec<Option<String>>>> = Arc::new(RwLock::new(v));
-
-    stream::iter(tracks)
-        .enumerate()
-        .for_each_concurrent(1, |(i, t)| {
-            let playlist = playlist.clone();
-            let client = client.clone();
-            let downloader = downloader.clone();
-            async move {
-                let t = client.get_track(t.id.to_string().as_str()).await.unwrap();
-                println!("{}/{}: {}", i + 1, n, t.title);
-                let path = downloader
-                    .download_and_tag_track(&t, &t.album, Quality::Cd, false)
-                    .await
-                    .unwrap();
-                *playlist.write().await.get_mut(i).unwrap() =
-                    Some(path.1.to_str().unwrap().to_string());
-            }
-        })
-        .await;
-    let playlist: Vec<String> = playlist
-        .read()
-        .await
-        .iter()
-        .map(|v| v.clone().unwrap())
-        .collect();
-    let mut f = std::fs::File::create(format!("{DIR}/favorites.m3u")).unwrap();
-    write!(f, "{}", playlist.join("\n")).unwrap();
+async fn download_playlists(client: &Client, downloader: &Downloader, options: &Options) {
+    let playlists = client.get_user_playlists().await.unwrap();
+    let n = playlists.len();
+    for (i, playlist) in playlists.into_iter().enumerate() {
+        println!("{}/{}: {}", i + 1, n, playlist.name);
+        let playlist = client.get_playlist(&playlist.id.to_string()).await.unwrap();
+        for track in playlist.tracks.iter().filter(|t| t.streamable) {
+            println!("  {track}");
+            downloader
+                .download_and_tag_track(track, &track.album, options.quality.clone(), options.force)
+                .await
+                .unwrap();
+        }
+    }
 }
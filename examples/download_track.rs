@@ -0,0 +1,29 @@
+#![allow(clippy::unwrap_used)]
+
+const DIR: &str = "music";
+
+use qobuz::{auth::Credentials, downloader::Downloader, quality::Quality, Client};
+
+/// Download the track whose ID is given as the first argument, defaulting to "Let It Be", and
+/// print the location it was written to.
+#[tokio::main]
+async fn main() {
+    let track_id = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "129342731".to_string());
+    let client = Client::new(Credentials::from_env().unwrap()).await.unwrap();
+    let track = client.get_track(&track_id).await.unwrap();
+
+    let downloader = Downloader::new(client, DIR);
+    let (_, path) = downloader
+        .download_and_tag_track_with(&track, &track.album, Quality::Cd, false, |progress| {
+            if progress.done {
+                println!("Done");
+            } else if let Some(fraction) = progress.fraction() {
+                println!("{:.0}%", fraction * 100.0);
+            }
+        })
+        .await
+        .unwrap();
+    println!("Downloaded {track} to {}", path.display());
+}
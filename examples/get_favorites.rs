@@ -26,7 +26,7 @@ async fn main() {
             continue;
         }
         println!("== {} ==", playlist.name);
-        for track in playlist.tracks.items {
+        for track in playlist.tracks {
             println!("{track}");
         }
     }
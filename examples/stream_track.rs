@@ -0,0 +1,41 @@
+#![allow(clippy::unwrap_used)]
+
+use futures::StreamExt;
+use qobuz::{
+    auth::Credentials,
+    link::{parse_url, ItemKind},
+    quality::Quality,
+    Client,
+};
+use std::io::{ErrorKind, Write};
+
+/// Write the track whose Qobuz URL is given as the first argument to stdout, in the quality
+/// given as the optional second argument, defaulting to CD quality. This allows listening to a
+/// track without saving it, e.g. with `cargo run --example stream_track <url> | mpv -`.
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let url = args
+        .next()
+        .expect("Usage: stream_track <track url> [quality]");
+    let quality: Quality = args.next().map_or(Quality::Cd, |q| q.parse().unwrap());
+    let (kind, id) = parse_url(&url).unwrap();
+    if kind != ItemKind::Track {
+        eprintln!("Only tracks can be streamed, but {url} is a {kind:?} URL");
+        std::process::exit(1);
+    }
+
+    let client = Client::new(Credentials::from_env().unwrap()).await.unwrap();
+    let mut stream = client.stream_track(&id, quality).await.unwrap();
+    let mut stdout = std::io::stdout().lock();
+    while let Some(chunk) = stream.next().await {
+        match stdout
+            .write_all(&chunk.unwrap())
+            .and_then(|()| stdout.flush())
+        {
+            // The player was closed, so there's nobody left to stream to.
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => return,
+            res => res.unwrap(),
+        }
+    }
+}
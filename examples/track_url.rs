@@ -0,0 +1,35 @@
+#![allow(clippy::unwrap_used)]
+
+use qobuz::{
+    auth::Credentials,
+    link::{parse_url, ItemKind},
+    quality::Quality,
+    Client,
+};
+
+/// Print the signed file URL of the track whose Qobuz URL is given as the first argument, in the
+/// quality given as the optional second argument, defaulting to CD quality, without downloading
+/// it. The URL is printed alone on stdout so that it can be passed to other tools, while the
+/// delivered quality and whether only a sample is served are printed on stderr.
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let url = args.next().expect("Usage: track_url <track url> [quality]");
+    let quality: Quality = args.next().map_or(Quality::Cd, |q| q.parse().unwrap());
+    let (kind, id) = parse_url(&url).unwrap();
+    if kind != ItemKind::Track {
+        eprintln!("Only tracks have a file URL, but {url} is a {kind:?} URL");
+        std::process::exit(1);
+    }
+
+    let client = Client::new(Credentials::from_env().unwrap()).await.unwrap();
+    let file = client.get_track_file(&id, quality.clone()).await.unwrap();
+    println!("{}", file.url);
+    eprintln!("Requested {quality}, delivered {}", file.quality);
+    if file.sample {
+        eprintln!("Only a sample of the track is served");
+    }
+    for restriction in &file.restrictions {
+        eprintln!("Restriction: {}", restriction.code);
+    }
+}
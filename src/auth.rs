@@ -1,4 +1,4 @@
-use super::{do_request, make_http_client};
+use super::{make_http_client, HttpOptions, DEFAULT_REQUEST_TIMEOUT};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::env;
@@ -6,16 +6,46 @@ use std::env::VarError;
 use thiserror::Error;
 
 /// Credentials for Qobuz.
+///
+/// Qobuz expects the MD5 hash of the password rather than the password itself, so `password`
+/// must already be hashed when building `Credentials` directly. Use `from_plaintext` to hash a
+/// plaintext password.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Credentials {
     pub email: String,
+    /// The MD5 hash of the password, as a lowercase hexadecimal string.
     pub password: String,
     pub app_id: String,
     pub secret: String,
 }
 
 impl Credentials {
-    /// Get the credentials from environment variables (`QOBUZ_*`).
+    /// Create credentials from a plaintext password, which is hashed as Qobuz expects.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use qobuz::auth::Credentials;
+    /// let credentials = Credentials::from_plaintext("me@example.com", "hunter2", "123456789", "secret");
+    /// assert_eq!(credentials.password, "2ab96390c7dbe3439de74d0c9b0b1767");
+    /// ```
+    #[must_use]
+    pub fn from_plaintext(
+        email: &str,
+        plaintext_password: &str,
+        app_id: &str,
+        secret: &str,
+    ) -> Self {
+        Self {
+            email: email.to_string(),
+            password: format!("{:x}", md5::compute(plaintext_password)),
+            app_id: app_id.to_string(),
+            secret: secret.to_string(),
+        }
+    }
+
+    /// Get the credentials from environment variables (`QOBUZ_*`). `QOBUZ_PASSWORD` must be the
+    /// MD5 hash of the password.
     ///
     /// # Errors
     ///
@@ -30,20 +60,40 @@ impl Credentials {
     }
 }
 
-pub(super) async fn get_user_auth_token(credentials: &Credentials) -> Result<String, LoginError> {
-    let client = make_http_client(&credentials.app_id, None);
+/// Log into Qobuz, returning the user auth token and the `user` object of the response.
+pub(super) async fn login(
+    credentials: &Credentials,
+    options: &HttpOptions,
+) -> Result<(String, Value), LoginError> {
+    let client = make_http_client(&credentials.app_id, None, options);
     let params = [
         ("email", credentials.email.as_str()),
         ("password", credentials.password.as_str()),
         ("app_id", credentials.app_id.as_str()),
     ];
-    let resp: Value = do_request(&client, "user/login", &params)
-        .await
-        .map_err(|e| match e.status() {
+    let res = client
+        .get(format!("{}user/login", options.api_url()))
+        .query(&params)
+        .timeout(DEFAULT_REQUEST_TIMEOUT)
+        .send()
+        .await?;
+    if let Err(e) = res.error_for_status_ref() {
+        // The body of error responses explains why the login was rejected.
+        let message = res
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|v| v.get("message")?.as_str().map(str::to_string))
+            .unwrap_or_default();
+        return Err(match e.status() {
+            _ if is_account_locked(&message) => LoginError::AccountLocked(message),
             Some(reqwest::StatusCode::UNAUTHORIZED) => LoginError::InvalidCredentials,
             Some(reqwest::StatusCode::BAD_REQUEST) => LoginError::InvalidAppId,
+            Some(reqwest::StatusCode::FORBIDDEN) => LoginError::Forbidden(message),
             _ => LoginError::ReqwestError(e),
-        })?;
+        });
+    }
+    let resp: Value = res.json().await?;
     // verify json["user"]["credential"]["parameters"] exists.
     // If not, we are authenticating into a free account which can't download tracks.
     if resp
@@ -54,18 +104,25 @@ pub(super) async fn get_user_auth_token(credentials: &Credentials) -> Result<Str
     {
         return Err(LoginError::FreeAccount);
     }
-    match resp.get("user_auth_token") {
-        Some(Value::String(uat)) => Ok(uat.to_string()),
-        None | Some(_) => Err(LoginError::NoUserAuthToken),
-    }
+    let uat = match resp.get("user_auth_token") {
+        Some(Value::String(uat)) => uat.to_string(),
+        None | Some(_) => return Err(LoginError::NoUserAuthToken),
+    };
+    let user = resp.get("user").cloned().unwrap_or(Value::Null);
+    Ok((uat, user))
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum LoginError {
     #[error("invalid credentials")]
     InvalidCredentials,
     #[error("invialid app id")]
     InvalidAppId,
+    #[error("account locked: `{0}`")]
+    AccountLocked(String),
+    #[error("login forbidden, e.g. because of a region restriction: `{0}`")]
+    Forbidden(String),
     #[error("reqwest error `{0}`")]
     ReqwestError(#[from] reqwest::Error),
     #[error("no user auth token")]
@@ -73,3 +130,25 @@ pub enum LoginError {
     #[error("tried to authenticate into a free account which can't download tracks")]
     FreeAccount,
 }
+
+/// Whether the message of a rejected login says that the account is locked. The whole word is
+/// matched so that e.g. "blocked" or "unlock" don't count.
+fn is_account_locked(message: &str) -> bool {
+    message
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.eq_ignore_ascii_case("locked"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_account_locked() {
+        assert!(is_account_locked("Your account is locked."));
+        assert!(is_account_locked("Account locked: too many attempts"));
+        assert!(!is_account_locked("Access blocked in your region"));
+        assert!(!is_account_locked("Please unlock your account"));
+        assert!(!is_account_locked(""));
+    }
+}
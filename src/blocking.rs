@@ -0,0 +1,471 @@
+//! A blocking API mirroring the async one, for applications that don't run an async runtime.
+//!
+//! Each blocking type wraps its async counterpart and runs its futures to completion on a
+//! runtime owned by the `Client`, so it must not be used from within an async runtime.
+//!
+//! # Example
+//!
+//! ```
+//! use qobuz::{auth::Credentials, blocking::Client};
+//! let credentials = Credentials::from_env().unwrap();
+//! let client = Client::new(credentials).unwrap();
+//! // Get information on "Abbey Road"
+//! let album = client.get_album("trrcz9pvaaz6b").unwrap();
+//! ```
+
+use crate::{
+    auth::{Credentials, LoginError},
+    downloader::{
+        AlbumDownloadProgress, ArtistDownloadProgress, DownloadError, ProgressCallback,
+        TrackDownloadProgress,
+    },
+    quality::Quality,
+    types::{
+        extra::{ExtraFlag, RootEntity, WithExtra, WithoutExtra},
+        traits::Favoritable,
+        Album, Array, Artist, Availability, Credit, Playlist, QobuzType, ReleaseType, SizeEstimate,
+        Track, TrackFile, UserInfo,
+    },
+    ApiError,
+};
+use core::fmt::Display;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+use tokio::runtime::Runtime;
+
+/// A blocking `crate::Client`.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Arc<Runtime>,
+}
+
+impl Client {
+    /// Create a new `Client`, logging in with the given credentials.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use qobuz::{auth::Credentials, blocking::Client};
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials).unwrap();
+    /// ```
+    pub fn new(credentials: Credentials) -> Result<Self, ClientError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let inner = runtime.block_on(crate::Client::new(credentials))?;
+        Ok(Self {
+            inner,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// See `crate::Client::public`.
+    pub fn public(app_id: &str) -> Result<Self, ClientError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            inner: crate::Client::public(app_id),
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Get the wrapped async client.
+    #[must_use]
+    pub const fn as_async(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// See `crate::Client::with_default_quality`.
+    #[must_use]
+    pub fn with_default_quality(self, quality: Quality) -> Self {
+        Self {
+            inner: self.inner.with_default_quality(quality),
+            ..self
+        }
+    }
+
+    /// See `crate::Client::with_response_cache`.
+    #[must_use]
+    pub fn with_response_cache(self, capacity: usize) -> Self {
+        Self {
+            inner: self.inner.with_response_cache(capacity),
+            ..self
+        }
+    }
+
+    /// See `crate::Client::get_user_info`.
+    pub fn get_user_info(&self) -> Result<UserInfo, ApiError> {
+        self.inner.get_user_info()
+    }
+
+    /// See `crate::Client::max_allowed_quality`.
+    #[must_use]
+    pub fn max_allowed_quality(&self) -> Option<Quality> {
+        self.inner.max_allowed_quality()
+    }
+
+    /// See `crate::Client::clamp_quality`.
+    #[must_use]
+    pub fn clamp_quality(&self, requested: Quality) -> Quality {
+        self.inner.clamp_quality(requested)
+    }
+
+    /// See `crate::Client::logout`.
+    pub fn logout(self) -> Result<(), ApiError> {
+        self.runtime.block_on(self.inner.logout())
+    }
+
+    /// See `crate::Client::get_track_file_url`.
+    pub fn get_track_file_url(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<url::Url, ApiError> {
+        self.block_on(self.inner.get_track_file_url(track_id, quality))
+    }
+
+    /// See `crate::Client::get_track_file_url_with_expiry`.
+    pub fn get_track_file_url_with_expiry(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<(url::Url, Option<chrono::DateTime<chrono::Utc>>), ApiError> {
+        self.block_on(self.inner.get_track_file_url_with_expiry(track_id, quality))
+    }
+
+    /// See `crate::Client::get_track_file`.
+    pub fn get_track_file(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<TrackFile, ApiError> {
+        self.block_on(self.inner.get_track_file(track_id, quality))
+    }
+
+    /// See `crate::Client::get_streamable_track_file`.
+    pub fn get_streamable_track_file(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<TrackFile, ApiError> {
+        self.block_on(self.inner.get_streamable_track_file(track_id, quality))
+    }
+
+    /// See `crate::Client::check_track_availability`.
+    pub fn check_track_availability(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<Availability, ApiError> {
+        self.block_on(self.inner.check_track_availability(track_id, quality))
+    }
+
+    /// See `crate::Client::get_user_favorites`.
+    pub fn get_user_favorites<T: QobuzType + DeserializeOwned + Favoritable>(
+        &self,
+    ) -> Result<Vec<T>, ApiError> {
+        self.block_on(self.inner.get_user_favorites())
+    }
+
+    /// See `crate::Client::is_favorite`.
+    pub fn is_favorite<T: QobuzType + Favoritable>(&self, id: &str) -> Result<bool, ApiError> {
+        self.block_on(self.inner.is_favorite::<T>(id))
+    }
+
+    /// See `crate::Client::get_user_playlists`.
+    pub fn get_user_playlists(&self) -> Result<Vec<Playlist<WithoutExtra>>, ApiError> {
+        self.block_on(self.inner.get_user_playlists())
+    }
+
+    /// See `crate::Client::get_featured_playlists`.
+    pub fn get_featured_playlists(
+        &self,
+        tag: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<Playlist<WithoutExtra>>, ApiError> {
+        self.block_on(self.inner.get_featured_playlists(tag, limit))
+    }
+
+    /// See `crate::Client::get_page`.
+    pub fn get_page<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+        key: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Array<T>, ApiError> {
+        self.block_on(self.inner.get_page(path, params, key, offset, limit))
+    }
+
+    /// See `crate::Client::get_track`.
+    pub fn get_track(&self, track_id: &str) -> Result<Track<WithExtra>, ApiError> {
+        self.block_on(self.inner.get_track(track_id))
+    }
+
+    /// See `crate::Client::get_track_with`.
+    pub fn get_track_with<EF>(&self, track_id: &str) -> Result<Track<EF>, ApiError>
+    where
+        EF: ExtraFlag<Album<WithoutExtra>>,
+        Track<EF>: RootEntity + DeserializeOwned,
+    {
+        self.block_on(self.inner.get_track_with(track_id))
+    }
+
+    /// See `crate::Client::get_track_credits`.
+    pub fn get_track_credits(&self, track_id: &str) -> Result<Vec<Credit>, ApiError> {
+        self.block_on(self.inner.get_track_credits(track_id))
+    }
+
+    /// See `crate::Client::get_track_with_full_album`.
+    pub fn get_track_with_full_album(
+        &self,
+        track_id: &str,
+    ) -> Result<(Track<WithExtra>, Album<WithExtra>), ApiError> {
+        self.block_on(self.inner.get_track_with_full_album(track_id))
+    }
+
+    /// See `crate::Client::get_tracks`.
+    pub fn get_tracks(
+        &self,
+        track_ids: &[&str],
+    ) -> Result<(Vec<Track<WithExtra>>, Vec<String>), ApiError> {
+        self.block_on(self.inner.get_tracks(track_ids))
+    }
+
+    /// See `crate::Client::estimate_download_size`.
+    pub fn estimate_download_size<EF>(
+        &self,
+        tracks: &[Track<EF>],
+        quality: Quality,
+        mode: SizeEstimate,
+    ) -> Result<u64, ApiError>
+    where
+        EF: ExtraFlag<Album<WithoutExtra>>,
+        EF::Extra: Sync,
+    {
+        self.block_on(self.inner.estimate_download_size(tracks, quality, mode))
+    }
+
+    /// See `crate::Client::get_playlist`.
+    pub fn get_playlist(&self, playlist_id: &str) -> Result<Playlist<WithExtra>, ApiError> {
+        self.block_on(self.inner.get_playlist(playlist_id))
+    }
+
+    /// See `crate::Client::get_playlist_tracks`.
+    pub fn get_playlist_tracks(
+        &self,
+        playlist_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Array<Track<WithExtra>>, ApiError> {
+        self.block_on(self.inner.get_playlist_tracks(playlist_id, offset, limit))
+    }
+
+    /// See `crate::Client::get_album`.
+    pub fn get_album(&self, album_id: &str) -> Result<Album<WithExtra>, ApiError> {
+        self.block_on(self.inner.get_album(album_id))
+    }
+
+    /// See `crate::Client::get_artist`.
+    pub fn get_artist(&self, artist_id: &str) -> Result<Artist<WithExtra>, ApiError> {
+        self.block_on(self.inner.get_artist(artist_id))
+    }
+
+    /// See `crate::Client::get_artist_albums`.
+    pub fn get_artist_albums(
+        &self,
+        artist_id: &str,
+        release_type: ReleaseType,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Array<Album<WithoutExtra>>, ApiError> {
+        self.block_on(
+            self.inner
+                .get_artist_albums(artist_id, release_type, offset, limit),
+        )
+    }
+
+    /// See `crate::Client::get_all_artist_tracks`.
+    pub fn get_all_artist_tracks(
+        &self,
+        artist_id: &str,
+    ) -> Result<Vec<Track<WithExtra>>, ApiError> {
+        self.block_on(self.inner.get_all_artist_tracks(artist_id))
+    }
+
+    /// See `crate::Client::get_raw`.
+    pub fn get_raw(&self, path: &str, params: &[(&str, &str)]) -> Result<Value, ApiError> {
+        self.block_on(self.inner.get_raw(path, params))
+    }
+}
+
+/// A blocking `crate::downloader::Downloader`.
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    inner: crate::downloader::Downloader,
+    runtime: Arc<Runtime>,
+}
+
+impl Downloader {
+    /// Create a new `Downloader` which will use the given `Client` to download to the given
+    /// `Path`.
+    ///
+    /// The async downloader's settings can be changed with `map`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use qobuz::{auth::Credentials, blocking::{Client, Downloader}, quality::Quality};
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials).unwrap();
+    /// let downloader = Downloader::new(client.clone(), "music")
+    ///     .map(|downloader| downloader.verify_existing(true));
+    /// // Download "Abbey Road", keeping existing files.
+    /// let album = client.get_album("trrcz9pvaaz6b").unwrap();
+    /// downloader
+    ///     .download_and_tag_album(&album, Quality::Mp3, false)
+    ///     .unwrap();
+    /// ```
+    #[must_use]
+    pub fn new(client: Client, root: impl AsRef<Path>) -> Self {
+        Self {
+            inner: crate::downloader::Downloader::new(client.inner, root),
+            runtime: client.runtime,
+        }
+    }
+
+    /// Change the settings of the wrapped async downloader.
+    #[must_use]
+    pub fn map(
+        self,
+        f: impl FnOnce(crate::downloader::Downloader) -> crate::downloader::Downloader,
+    ) -> Self {
+        Self {
+            inner: f(self.inner),
+            ..self
+        }
+    }
+
+    /// See `crate::downloader::Downloader::download_and_tag_track_with`.
+    pub fn download_and_tag_track_with<EF1, EF2>(
+        &self,
+        track: &Track<EF1>,
+        album: &Album<EF2>,
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<TrackDownloadProgress>,
+    ) -> Result<(PathBuf, PathBuf), DownloadError>
+    where
+        EF1: ExtraFlag<Album<WithoutExtra>>,
+        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
+        EF1::Extra: Sync,
+        EF2::Extra: Sync,
+    {
+        self.runtime.block_on(
+            self.inner
+                .download_and_tag_track_with(track, album, quality, force, callback),
+        )
+    }
+
+    /// See `crate::downloader::Downloader::download_and_tag_track`.
+    pub fn download_and_tag_track<EF1, EF2>(
+        &self,
+        track: &Track<EF1>,
+        album: &Album<EF2>,
+        quality: Quality,
+        force: bool,
+    ) -> Result<(PathBuf, PathBuf), DownloadError>
+    where
+        EF1: ExtraFlag<Album<WithoutExtra>>,
+        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
+        EF1::Extra: Sync,
+        EF2::Extra: Sync,
+    {
+        self.download_and_tag_track_with(track, album, quality, force, |_| {})
+    }
+
+    /// See `crate::downloader::Downloader::download_and_tag_album_with`.
+    pub fn download_and_tag_album_with(
+        &self,
+        album: &Album<WithExtra>,
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<AlbumDownloadProgress>,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+        self.runtime.block_on(
+            self.inner
+                .download_and_tag_album_with(album, quality, force, callback),
+        )
+    }
+
+    /// See `crate::downloader::Downloader::download_and_tag_album`.
+    pub fn download_and_tag_album(
+        &self,
+        album: &Album<WithExtra>,
+        quality: Quality,
+        force: bool,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+        self.download_and_tag_album_with(album, quality, force, |_| {})
+    }
+
+    /// See `crate::downloader::Downloader::download_and_tag_album_tracks`.
+    pub fn download_and_tag_album_tracks(
+        &self,
+        album: &Album<WithExtra>,
+        track_ids: &[u64],
+        quality: Quality,
+        force: bool,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+        self.runtime.block_on(
+            self.inner
+                .download_and_tag_album_tracks(album, track_ids, quality, force),
+        )
+    }
+
+    /// See `crate::downloader::Downloader::download_and_tag_artist_with`.
+    pub fn download_and_tag_artist_with(
+        &self,
+        artist: &Artist<WithExtra>,
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<ArtistDownloadProgress>,
+    ) -> Result<Vec<PathBuf>, DownloadError> {
+        self.runtime.block_on(
+            self.inner
+                .download_and_tag_artist_with(artist, quality, force, callback),
+        )
+    }
+
+    /// See `crate::downloader::Downloader::download_and_tag_artist`.
+    pub fn download_and_tag_artist(
+        &self,
+        artist: &Artist<WithExtra>,
+        quality: Quality,
+        force: bool,
+    ) -> Result<Vec<PathBuf>, DownloadError> {
+        self.download_and_tag_artist_with(artist, quality, force, |_| {})
+    }
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ClientError {
+    #[error("couldn't start the runtime `{0}`")]
+    RuntimeError(#[from] std::io::Error),
+    #[error("login error `{0}`")]
+    LoginError(#[from] LoginError),
+}
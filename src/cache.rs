@@ -0,0 +1,183 @@
+use crate::types::TrackFile;
+use bytes::Bytes;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A bounded in-memory cache of track files, keyed by track ID and quality ID.
+///
+/// Entries expire at the time given by the `etsp` parameter of the file URL. Files whose URL has
+/// no such parameter aren't cached, since their lifetime is unknown.
+#[derive(Debug)]
+pub(crate) struct TrackFileCache {
+    capacity: usize,
+    entries: HashMap<(String, u8), (TrackFile, SystemTime)>,
+}
+
+impl TrackFileCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Get the cached file of a track, if it hasn't expired yet.
+    pub(crate) fn get(&mut self, track_id: &str, quality_id: u8) -> Option<TrackFile> {
+        let key = (track_id.to_string(), quality_id);
+        match self.entries.get(&key) {
+            Some((file, expiry)) if *expiry > SystemTime::now() => Some(file.clone()),
+            Some(_) => {
+                self.entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Cache the file of a track, evicting expired entries and then the entry expiring the
+    /// soonest if the cache is full.
+    pub(crate) fn insert(&mut self, track_id: &str, quality_id: u8, file: TrackFile) {
+        let Some(expiry) = url_expiry(&file.url) else {
+            return;
+        };
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            let now = SystemTime::now();
+            self.entries.retain(|_, (_, expiry)| *expiry > now);
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some(key) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, expiry))| *expiry)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&key);
+            }
+        }
+        self.entries
+            .insert((track_id.to_string(), quality_id), (file, expiry));
+    }
+}
+
+/// The key of a cached response: the request path and its parameters.
+pub(crate) type ResponseKey = (String, Vec<(String, String)>);
+
+/// A bounded in-memory cache of API responses with their `ETag`, used to revalidate them with
+/// `If-None-Match` instead of downloading them again.
+///
+/// When the cache is full, the least recently used entry is evicted.
+#[derive(Debug)]
+pub(crate) struct ResponseCache {
+    capacity: usize,
+    /// Incremented on each access, giving the order in which entries were last used.
+    clock: u64,
+    entries: HashMap<ResponseKey, CachedResponse>,
+    /// The keys of the entries by the time they were last used, least recently used first.
+    by_last_use: BTreeMap<u64, ResponseKey>,
+}
+
+#[derive(Debug)]
+struct CachedResponse {
+    etag: String,
+    body: Bytes,
+    last_used: u64,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            clock: 0,
+            entries: HashMap::new(),
+            by_last_use: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn key(path: &str, params: &[(&str, &str)]) -> ResponseKey {
+        let params = params
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        (path.to_string(), params)
+    }
+
+    /// Get the `ETag` and body of a cached response.
+    pub(crate) fn get(&mut self, key: &ResponseKey) -> Option<(String, Bytes)> {
+        let entry = self.entries.get_mut(key)?;
+        self.clock += 1;
+        let key = self
+            .by_last_use
+            .remove(&entry.last_used)
+            .unwrap_or_else(|| key.clone());
+        self.by_last_use.insert(self.clock, key);
+        entry.last_used = self.clock;
+        Some((entry.etag.clone(), entry.body.clone()))
+    }
+
+    /// Cache a response, evicting the least recently used entry if the cache is full.
+    pub(crate) fn insert(&mut self, key: ResponseKey, etag: String, body: Bytes) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(&key);
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.by_last_use.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        let entry = CachedResponse {
+            etag,
+            body,
+            last_used: self.clock,
+        };
+        self.by_last_use.insert(self.clock, key.clone());
+        self.entries.insert(key, entry);
+    }
+
+    /// Remove a cached response, e.g. when the server stopped sending an `ETag` for it.
+    pub(crate) fn remove(&mut self, key: &ResponseKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.by_last_use.remove(&entry.last_used);
+        }
+    }
+}
+
+/// Get the expiry time of a file URL from its `etsp` parameter, a UNIX timestamp.
+pub(crate) fn url_expiry(url: &url::Url) -> Option<SystemTime> {
+    let (_, etsp) = url.query_pairs().find(|(key, _)| key == "etsp")?;
+    let etsp: u64 = etsp.parse().ok()?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(etsp))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_response_cache_evicts_least_recently_used() {
+        let mut cache = ResponseCache::new(2);
+        let keys: Vec<_> = ["a", "b", "c"]
+            .into_iter()
+            .map(|path| ResponseCache::key(path, &[]))
+            .collect();
+        cache.insert(keys[0].clone(), "0".to_string(), Bytes::new());
+        cache.insert(keys[1].clone(), "1".to_string(), Bytes::new());
+        // Using the first entry makes the second one the least recently used.
+        assert!(cache.get(&keys[0]).is_some());
+        cache.insert(keys[2].clone(), "2".to_string(), Bytes::new());
+        assert!(cache.get(&keys[1]).is_none());
+        assert_eq!(cache.get(&keys[0]).unwrap().0, "0");
+        assert_eq!(cache.get(&keys[2]).unwrap().0, "2");
+
+        cache.remove(&keys[0]);
+        assert!(cache.get(&keys[0]).is_none());
+        assert_eq!(cache.entries.len(), cache.by_last_use.len());
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+/// How track paths are written in m3u playlists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathStyle {
+    /// Paths relative to the directory of the playlist, e.g. `../Artist/Album/01 - Track.flac`,
+    /// so that players resolving them from the playlist's location find them.
+    #[default]
+    Relative,
+    /// Canonical absolute paths, e.g. for media servers importing the playlist. The tracks must
+    /// exist when writing the playlist.
+    Absolute,
+}
+
+/// Write an m3u playlist of the tracks at `track_paths` to `m3u_path`, creating its directory
+/// if needed. Track paths are written as specified by `style`.
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+/// # use std::path::Path;
+/// # let credentials = Credentials::from_env().unwrap();
+/// # let client = Client::new(credentials).await.unwrap();
+/// # let downloader = Downloader::new(client.clone(), Path::new("music"));
+/// use qobuz::downloader::m3u::{write_m3u, PathStyle};
+/// // Download "Abbey Road" and write a playlist of its tracks.
+/// let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+/// let (_, track_paths) = downloader
+///     .download_and_tag_album(&album, Quality::Mp3, false)
+///     .await
+///     .unwrap();
+/// write_m3u(
+///     Path::new("music/playlists/Abbey Road.m3u"),
+///     &track_paths,
+///     PathStyle::Relative,
+/// )
+/// .unwrap();
+/// # })
+/// ```
+pub fn write_m3u(
+    m3u_path: &Path,
+    track_paths: &[PathBuf],
+    style: PathStyle,
+) -> Result<(), std::io::Error> {
+    write_playlist(m3u_path, track_paths, style, "", |file, track_path| {
+        writeln!(file, "{}", track_path.display())
+    })
+}
+
+/// Write an m3u8 playlist, i.e. an m3u playlist explicitly encoded in UTF-8, like `write_m3u`.
+/// Players may assume plain m3u playlists are encoded in Latin-1, so m3u8 playlists are better
+/// suited to non-ASCII titles, and should be given the `.m3u8` extension.
+///
+/// Paths that aren't valid UTF-8 can't be written and result in an `InvalidData` error rather
+/// than being altered. If `bom` is set, the file starts with a byte order mark, which some
+/// Windows players need to detect the encoding.
+pub fn write_m3u8(
+    m3u_path: &Path,
+    track_paths: &[PathBuf],
+    style: PathStyle,
+    bom: bool,
+) -> Result<(), std::io::Error> {
+    let header = if bom { UTF8_BOM } else { "" };
+    write_playlist(m3u_path, track_paths, style, header, |file, track_path| {
+        let track_path = track_path.to_str().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("`{}` isn't valid UTF-8", track_path.display()),
+            )
+        })?;
+        writeln!(file, "{track_path}")
+    })
+}
+
+/// The byte order mark optionally written at the start of m3u8 playlists.
+const UTF8_BOM: &str = "\u{feff}";
+
+/// Write `header` then the tracks at `track_paths` to the playlist at `m3u_path` with
+/// `write_line`, resolving their paths as specified by `style`.
+fn write_playlist(
+    m3u_path: &Path,
+    track_paths: &[PathBuf],
+    style: PathStyle,
+    header: &str,
+    mut write_line: impl FnMut(&mut dyn Write, &Path) -> Result<(), std::io::Error>,
+) -> Result<(), std::io::Error> {
+    let m3u_dir = std::path::absolute(m3u_path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    std::fs::create_dir_all(&m3u_dir)?;
+    let mut file = std::io::BufWriter::new(std::fs::File::create(m3u_path)?);
+    file.write_all(header.as_bytes())?;
+    for track_path in track_paths {
+        let track_path = match style {
+            PathStyle::Relative => relative_path(&m3u_dir, &std::path::absolute(track_path)?),
+            PathStyle::Absolute => track_path.canonicalize()?,
+        };
+        write_line(&mut file, &track_path)?;
+    }
+    file.flush()
+}
+
+/// Get the path leading from the directory `from` to `to`, both being absolute and normalized.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    from[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .chain(to[common..].iter().copied())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(
+                Path::new("/music/playlists"),
+                Path::new("/music/Artist - Album/01. Track.flac")
+            ),
+            Path::new("../Artist - Album/01. Track.flac")
+        );
+        assert_eq!(
+            relative_path(Path::new("/music"), Path::new("/music/Album/01.flac")),
+            Path::new("Album/01.flac")
+        );
+    }
+
+    #[test]
+    fn test_write_m3u() {
+        let root = std::env::temp_dir().join(format!("qobuz-m3u-{}", std::process::id()));
+        let m3u_path = root.join("playlists").join("test.m3u");
+        let track_path = root.join("Artist - Album").join("01. Track.flac");
+        write_m3u(&m3u_path, &[track_path], PathStyle::Relative).unwrap();
+        let content = std::fs::read_to_string(&m3u_path).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(content, "../Artist - Album/01. Track.flac\n");
+    }
+
+    #[test]
+    fn test_write_m3u_absolute() {
+        let root = std::env::temp_dir().join(format!("qobuz-m3u-abs-{}", std::process::id()));
+        let m3u_path = root.join("playlists").join("test.m3u");
+        let track_path = root.join("Artist - Album").join("01. Track.flac");
+        std::fs::create_dir_all(track_path.parent().unwrap()).unwrap();
+        std::fs::write(&track_path, b"").unwrap();
+        write_m3u(
+            &m3u_path,
+            std::slice::from_ref(&track_path),
+            PathStyle::Absolute,
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(&m3u_path).unwrap();
+        let expected = format!("{}\n", track_path.canonicalize().unwrap().display());
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(content, expected);
+    }
+
+    #[test]
+    fn test_write_m3u8() {
+        let root = std::env::temp_dir().join(format!("qobuz-m3u8-{}", std::process::id()));
+        let m3u_path = root.join("test.m3u8");
+        let track_path = root
+            .join("Sigur Rós - Ágætis byrjun")
+            .join("Svefn-g-englar.flac");
+        write_m3u8(
+            &m3u_path,
+            std::slice::from_ref(&track_path),
+            PathStyle::Relative,
+            false,
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(&m3u_path).unwrap();
+        assert_eq!(content, "Sigur Rós - Ágætis byrjun/Svefn-g-englar.flac\n");
+        write_m3u8(&m3u_path, &[track_path], PathStyle::Relative, true).unwrap();
+        let content = std::fs::read_to_string(&m3u_path).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            content,
+            "\u{feff}Sigur Rós - Ágætis byrjun/Svefn-g-englar.flac\n"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Name of the manifest written in album directories, see `Downloader::write_manifest`.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// A list of the tracks of an album directory along with their size and hash, to verify them
+/// later, e.g. in backups.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub album_id: String,
+    pub tracks: Vec<ManifestEntry>,
+}
+
+/// A track of a `Manifest`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The location of the track relative to the album directory.
+    pub file: PathBuf,
+    /// The size of the track in bytes.
+    pub size: u64,
+    /// The SHA-256 hash of the track, as a lowercase hexadecimal string.
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Build the manifest of the tracks at `track_paths` in the album directory `album_path`,
+    /// hashing them.
+    pub fn new(album_id: &str, album_path: &Path, track_paths: &[PathBuf]) -> io::Result<Self> {
+        let tracks = track_paths
+            .iter()
+            .map(|track_path| {
+                let mut hasher = Sha256::new();
+                let size = io::copy(&mut File::open(track_path)?, &mut hasher)?;
+                Ok(ManifestEntry {
+                    file: track_path
+                        .strip_prefix(album_path)
+                        .unwrap_or(track_path)
+                        .to_path_buf(),
+                    size,
+                    sha256: format!("{:x}", hasher.finalize()),
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            album_id: album_id.to_string(),
+            tracks,
+        })
+    }
+
+    /// Write the manifest as JSON to `MANIFEST_FILE_NAME` in the album directory `album_path`.
+    pub fn write(&self, album_path: &Path) -> io::Result<()> {
+        let file = File::create(album_path.join(MANIFEST_FILE_NAME))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
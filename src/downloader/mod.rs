@@ -1,103 +1,298 @@
 use crate::{
+    link::ItemKind,
     quality::{FileExtension, Quality},
     types::{
         extra::{ExtraFlag, WithExtra, WithoutExtra},
-        Album, Array, Track,
+        Album, Array, Artist, Availability, Playlist, Track, UnavailabilityReason,
     },
-    ApiError,
+    with_idle_timeout, ApiError,
 };
+use chrono::Utc;
 use futures::{stream, StreamExt};
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{self, AtomicU64},
+        Arc,
+    },
+    time::Instant,
+};
 use thiserror::Error;
-use tokio::fs::OpenOptions;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+use unicode_normalization::UnicodeNormalization;
+pub mod m3u;
+pub mod manifest;
+pub mod post_processing;
+pub mod queue;
+pub mod replaygain;
 pub mod tagging;
-use tagging::{tag_track, TaggingError};
+use m3u::{write_m3u, write_m3u8, PathStyle};
+use manifest::Manifest;
+use post_processing::{PostProcessingError, PostProcessor};
+use queue::{DownloadQueue, QueueProgress};
+use replaygain::LoudnessAnalyzer;
+use tagging::{tag_replaygain, tag_track, tag_track_replaygain, Tagger, TaggingError};
 
 #[derive(Debug, Clone)]
 pub struct Downloader {
     client: crate::Client,
     root: Box<Path>,
+    verify_existing: bool,
+    dry_run: bool,
+    post_processor: Option<Arc<dyn PostProcessor>>,
+    tagger: Option<Arc<dyn Tagger>>,
+    tag: bool,
+    qobuz_replaygain: bool,
+    single_layout: SingleLayout,
+    fetch_full_album: bool,
+    playlist_m3u_format: String,
+    m3u8: bool,
+    m3u8_bom: bool,
+    loudness_analyzer: Option<Arc<dyn LoudnessAnalyzer>>,
+    verify_downloads: bool,
+    verify_etag_md5: bool,
+    resume: bool,
+    write_manifest: bool,
+    cancellation_token: Option<CancellationToken>,
+    staging_dir: Option<Box<Path>>,
 }
 
 impl Downloader {
     /// Create a new `Downloader` which will use the given `Client` to download to the given
     /// `Path`.
     ///
+    /// The root directory doesn't need to exist: it is created along with its parents when
+    /// needed, as are album directories, the staging directory and the directories of m3u
+    /// playlists.
+    ///
     /// # Example
     ///
     /// ```
     /// # use tokio_test;
     /// # tokio_test::block_on(async {
     /// use qobuz::{auth::Credentials, Client, downloader::Downloader};
-    /// use std::path::Path;
     /// let credentials = Credentials::from_env().unwrap();
     /// let client = Client::new(credentials).await.unwrap();
-    /// let root = Path::new("music");
-    /// let downloader = Downloader::new(client, root);
+    /// let downloader = Downloader::new(client, "music");
     /// # })
     /// ```
     #[must_use]
-    pub fn new(client: crate::Client, root: &Path) -> Self {
+    pub fn new(client: crate::Client, root: impl AsRef<Path>) -> Self {
         Self {
             client,
-            root: root.into(),
+            root: root.as_ref().into(),
+            verify_existing: false,
+            dry_run: false,
+            post_processor: None,
+            tagger: None,
+            tag: true,
+            qobuz_replaygain: false,
+            single_layout: SingleLayout::AlbumDir,
+            fetch_full_album: true,
+            playlist_m3u_format: DEFAULT_PLAYLIST_M3U_FORMAT.to_string(),
+            m3u8: false,
+            m3u8_bom: false,
+            loudness_analyzer: None,
+            verify_downloads: true,
+            verify_etag_md5: false,
+            resume: false,
+            write_manifest: false,
+            cancellation_token: None,
+            staging_dir: None,
         }
     }
 
-    /// Download and tag a track, returning the download locations of the album and track.
+    /// Set whether existing files should be checked when not forcing downloads.
+    ///
+    /// When enabled, an existing track file smaller than the file served by Qobuz, e.g. because a
+    /// previous download was interrupted, is downloaded again instead of being skipped. Files
+    /// can't be compared more precisely since tagging changes their size.
     ///
     /// # Example
     ///
     /// ```
     /// # use tokio_test;
     /// # tokio_test::block_on(async {
-    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
     /// # use std::path::Path;
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// # let root = Path::new("music");
-    /// let downloader = Downloader::new(client.clone(), root);
-    /// // Download "Let It Be", replacing the file if it already exists.
-    /// let track = client
-    ///     .get_track("129342731")
-    ///     .await
-    ///     .unwrap();
-    /// downloader
-    ///     .download_and_tag_track(&track, &track.album, Quality::Mp3, true)
-    ///     .await
-    ///     .unwrap();
+    /// let downloader = Downloader::new(client, Path::new("music")).verify_existing(true);
     /// # })
     /// ```
-    pub async fn download_and_tag_track<EF1, EF2>(
-        &self,
-        track: &Track<EF1>,
-        album: &Album<EF2>,
-        quality: Quality,
-        force: bool,
-    ) -> Result<(PathBuf, PathBuf), DownloadError>
-    where
-        EF1: ExtraFlag<Album<WithoutExtra>>,
-        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
-        EF1::Extra: Sync,
-        EF2::Extra: Sync,
-    {
-        let album_path = self.get_standard_album_location(album, true)?;
-        let track_path = self
-            .download_track(track, &album_path, quality, force)
-            .await?;
-        let cover_raw = reqwest::get(album.image.large.clone())
-            .await?
-            .bytes()
-            .await?;
-        let cover = audiotags::Picture::new(&cover_raw, audiotags::MimeType::Jpeg);
-        tag_track(track, &track_path, album, cover)?;
-        Ok((album_path, track_path))
+    #[must_use]
+    pub const fn verify_existing(mut self, verify_existing: bool) -> Self {
+        self.verify_existing = verify_existing;
+        self
     }
 
-    /// Download and tag an album, returning its download location.
+    /// Set whether downloaded files should be checked against the size announced by the server,
+    /// and against its MD5 hash when `verify_etag_md5` is enabled. Enabled by default.
+    ///
+    /// When a check fails, `DownloadError::IntegrityMismatch` is returned and the file is
+    /// removed, so that it is downloaded again on the next run.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, Path::new("music")).verify_downloads(false);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn verify_downloads(mut self, verify_downloads: bool) -> Self {
+        self.verify_downloads = verify_downloads;
+        self
+    }
+
+    /// Set whether `ETag`s that look like an MD5 hash, i.e. 32 hexadecimal digits, are used as
+    /// the hash of downloaded files when `verify_downloads` is enabled. Disabled by default.
+    ///
+    /// `ETag`s are opaque: the CDN serving the files may compute them otherwise, e.g. for
+    /// multipart uploads, in which case valid downloads would be rejected and removed. Only
+    /// enable this if the `ETag`s are known to be MD5 hashes of the files.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, "music").verify_etag_md5(true);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn verify_etag_md5(mut self, verify_etag_md5: bool) -> Self {
+        self.verify_etag_md5 = verify_etag_md5;
+        self
+    }
+
+    /// Set whether interrupted album downloads should be resumed when not forcing downloads.
+    ///
+    /// When enabled, the tracks downloaded so far are recorded in a `RESUME_FILE_NAME` file in
+    /// the album directory, which is removed once the album is complete. An album download then
+    /// starts again at the first track that wasn't recorded, without checking the previous ones
+    /// again, and the progress starts at that track.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, Path::new("music")).resume(true);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Stop downloads with `DownloadError::Cancelled` once `token` is cancelled, e.g. from a
+    /// "stop" button. The token is checked before each track and while receiving its data, and
+    /// the partially downloaded file is removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use tokio_util::sync::CancellationToken;
+    /// let token = CancellationToken::new();
+    /// let downloader =
+    ///     Downloader::new(client, Path::new("music")).cancellation_token(token.clone());
+    /// // Later, e.g. when the user clicks "stop":
+    /// token.cancel();
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Download new albums to a directory in `staging_dir` first, moving it to the root directory
+    /// only once all its tracks are downloaded and tagged, so that e.g. media servers watching
+    /// the root directory never see incomplete albums. Albums already in the root directory are
+    /// completed in place.
+    ///
+    /// The staging directory may be on another filesystem than the root directory, in which case
+    /// albums are copied, then removed from it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, "music").staging_dir("/tmp/qobuz");
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn staging_dir(mut self, staging_dir: impl AsRef<Path>) -> Self {
+        self.staging_dir = Some(staging_dir.as_ref().into());
+        self
+    }
+
+    /// Fail with `DownloadError::Cancelled` if the cancellation token was cancelled.
+    fn check_cancelled(&self) -> Result<(), DownloadError> {
+        match &self.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(DownloadError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Set whether a manifest listing the size and SHA-256 hash of each track should be written
+    /// to `manifest::MANIFEST_FILE_NAME` in album directories after album downloads, e.g. to
+    /// verify backups later.
+    ///
+    /// Tracks are hashed once they have been tagged, since tagging changes their content.
     ///
     /// # Example
     ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, Path::new("music")).write_manifest(true);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn write_manifest(mut self, write_manifest: bool) -> Self {
+        self.write_manifest = write_manifest;
+        self
+    }
+
+    /// Set whether downloads should only compute the locations files would be written to.
+    ///
+    /// In dry-run mode, no file or directory is created and no track file URL is queried, but the
+    /// download methods still return the locations of the album and tracks.
+    ///
+    /// # Example
     ///
     /// ```
     /// # use tokio_test;
@@ -106,139 +301,1834 @@ impl Downloader {
     /// # use std::path::Path;
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// # let root = Path::new("music");
-    /// # let downloader = Downloader::new(client.clone(), root);
-    /// // Download "Abbey Road", replacing files if they already exist.
+    /// let downloader = Downloader::new(client.clone(), Path::new("music")).dry_run(true);
+    /// // See where "Abbey Road" would be downloaded.
     /// let album = client
     ///     .get_album("trrcz9pvaaz6b")
     ///     .await
     ///     .unwrap();
-    /// downloader
-    ///     .download_and_tag_album(&album, Quality::Mp3, true)
+    /// let (album_path, track_paths) = downloader
+    ///     .download_and_tag_album(&album, Quality::Mp3, false)
     ///     .await
     ///     .unwrap();
     /// # })
-    pub async fn download_and_tag_album(
-        &self,
-        album: &Album<WithExtra>,
-        quality: Quality,
-        force: bool,
-    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
-        let album_path = self.get_standard_album_location(album, true)?;
-        let cover_raw = reqwest::get(album.image.large.clone())
-            .await?
-            .bytes()
-            .await?;
-        let cover = audiotags::Picture::new(&cover_raw, audiotags::MimeType::Jpeg);
-        let items = &album.tracks.items;
-
-        let track_paths: Vec<PathBuf> = stream::iter(items)
-            .then(|track| async {
-                let track_path = self
-                    .download_track(track, &album_path, quality.clone(), force)
-                    .await?;
-                tag_track(track, &track_path, album, cover.clone())?;
-                Ok(track_path)
-            })
-            .collect::<Vec<_>>()
-            .await
-            .into_iter()
-            .collect::<Result<_, DownloadError>>()?;
+    /// ```
+    #[must_use]
+    pub const fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
 
-        Ok((album_path, track_paths))
+    /// Set a `PostProcessor` to run on each track after it has been downloaded and tagged, e.g.
+    /// to transcode it. The returned track locations are the ones of the processed tracks.
+    ///
+    /// When not forcing downloads, tracks are skipped if their processed file exists, as given
+    /// by `PostProcessor::output_path`. Processors that don't know it in advance fall back to
+    /// the location before processing, so their tracks are downloaded again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::downloader::{post_processing::ExternalCommand, Downloader};
+    /// let downloader = Downloader::new(client, Path::new("music")).post_processor(
+    ///     ExternalCommand::new("ffmpeg", ["-i", "{input}", "-q:a", "0", "{output}"], "mp3"),
+    /// );
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn post_processor(mut self, post_processor: impl PostProcessor + 'static) -> Self {
+        self.post_processor = Some(Arc::new(post_processor));
+        self
     }
 
-    async fn download_track<EF>(
-        &self,
-        track: &Track<EF>,
-        album_path: &Path,
-        quality: Quality,
-        force: bool,
-    ) -> Result<PathBuf, DownloadError>
-    where
-        EF: ExtraFlag<Album<WithoutExtra>>,
-        EF::Extra: Sync,
-    {
-        let track_path = self.get_standard_track_location(track, album_path, &quality);
-        let mut out = match OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .create_new(!force) // (Shadows create and truncate)
-            .open(&track_path)
-            .await
-        {
-            Ok(v) => v,
-            Err(e) => {
-                return match e.kind() {
-                    std::io::ErrorKind::AlreadyExists => Ok(track_path),
-                    _ => Err(DownloadError::IoError(e)),
-                }
-            }
-        };
-        let mut bytes_stream = self
-            .client
-            .stream_track(&track.id.to_string(), quality)
-            .await?;
-        while let Some(item) = bytes_stream.next().await {
-            tokio::io::copy(&mut item?.as_ref(), &mut out).await?;
-        }
-        Ok(track_path)
+    /// Set the `Tagger` writing the tags of downloaded tracks, e.g. to write custom fields.
+    /// Tracks are tagged with `AudioTagsTagger` by default. See `tag` to leave them untagged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::downloader::{tagging::AudioTagsTagger, Downloader};
+    /// let downloader = Downloader::new(client, Path::new("music")).tagger(AudioTagsTagger);
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn tagger(mut self, tagger: impl Tagger + 'static) -> Self {
+        self.tagger = Some(Arc::new(tagger));
+        self
     }
 
-    // TODO: configurable path format
-    pub fn get_standard_album_location<EF>(
-        &self,
-        album: &Album<EF>,
-        ensure_exists: bool,
-    ) -> Result<PathBuf, std::io::Error>
-    where
-        EF: ExtraFlag<Array<Track<WithoutExtra>>>,
-    {
-        let mut path = self.root.to_path_buf();
-        path.push(format!(
-            "{} - {}",
-            sanitize_filename(&album.artist.name),
-            sanitize_filename(&album.title),
-        ));
-        if ensure_exists && !path.is_dir() {
-            std::fs::create_dir_all(&path)?;
-        }
-        Ok(path)
+    /// Set whether downloaded tracks should be tagged. Enabled by default.
+    ///
+    /// When disabled, tracks are left as served by Qobuz: neither the `Tagger` nor the
+    /// ReplayGain options write anything, and album covers aren't fetched, which speeds up bulk
+    /// downloads when tagging is done by another tool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, Path::new("music")).tag(false);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn tag(mut self, tag: bool) -> Self {
+        self.tag = tag;
+        self
     }
 
+    /// Set whether the ReplayGain values computed by Qobuz, see `Track::audio_info`, should be
+    /// written as ReplayGain track tags when tagging tracks. Disabled by default.
+    ///
+    /// This is much cheaper than a `LoudnessAnalyzer` but only covers tracks, and tracks whose
+    /// response has no audio info are left without ReplayGain tags.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, Path::new("music")).qobuz_replaygain(true);
+    /// # })
+    /// ```
     #[must_use]
-    pub fn get_standard_track_location<EF>(
-        &self,
-        track: &Track<EF>,
-        album_path: &Path,
-        quality: &Quality,
-    ) -> PathBuf
-    where
-        EF: ExtraFlag<Album<WithoutExtra>>,
-    {
-        let mut path = album_path.to_path_buf();
-        path.push(sanitize_filename(&track.title));
-        path.set_extension(FileExtension::from(quality).to_string());
-        path
+    pub const fn qobuz_replaygain(mut self, qobuz_replaygain: bool) -> Self {
+        self.qobuz_replaygain = qobuz_replaygain;
+        self
     }
-}
 
-#[derive(Debug, Error)]
-pub enum DownloadError {
-    #[error("tagging error `{0}`")]
-    TaggingError(#[from] TaggingError),
-    #[error("IO error `{0}`")]
-    IoError(#[from] std::io::Error),
-    #[error("reqwest error `{0}`")]
+    /// Set where tracks downloaded on their own, e.g. with `download_and_tag_track` or as part of
+    /// a playlist, are placed. Tracks go to their album directory by default.
+    ///
+    /// Album and artist downloads always use album directories.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::downloader::{Downloader, SingleLayout};
+    /// let downloader =
+    ///     Downloader::new(client, Path::new("music")).single_layout(SingleLayout::ArtistDir);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn single_layout(mut self, single_layout: SingleLayout) -> Self {
+        self.single_layout = single_layout;
+        self
+    }
+
+    /// Set whether `download` fetches the full album of tracks before downloading them, which is
+    /// enabled by default.
+    ///
+    /// Tracks only embed a minimal version of their album, so fetching the full one makes the
+    /// tags of single tracks the same as when downloading their whole album, e.g. their total
+    /// number of tracks, at the cost of an additional request per track. The album isn't fetched
+    /// when nothing gets tagged, i.e. with `tag(false)` or `dry_run(true)`. Disable it to always
+    /// use the embedded album instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, "music").fetch_full_album(false);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn fetch_full_album(mut self, fetch_full_album: bool) -> Self {
+        self.fetch_full_album = fetch_full_album;
+        self
+    }
+
+    /// Set the name of the m3u files written for playlists, without extension, which defaults to
+    /// `DEFAULT_PLAYLIST_M3U_FORMAT`. See `get_playlist_m3u_location`.
+    ///
+    /// The `{name}`, `{owner}`, `{id}` and `{date}` placeholders are replaced with the name of
+    /// the playlist, the name of its owner, its ID and its creation date, e.g. to tell apart
+    /// playlists with the same name. Downloading a playlist fails if this gives an empty name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, "music").playlist_m3u_format("{owner} - {name}");
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn playlist_m3u_format(mut self, format: impl Into<String>) -> Self {
+        self.playlist_m3u_format = format.into();
+        self
+    }
+
+    /// Set whether playlists should be written as m3u8 files, with the `.m3u8` extension, see
+    /// `m3u::write_m3u8`. This is better for non-ASCII titles but some old players only support
+    /// m3u files, so it is disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, "music").m3u8(true);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn m3u8(mut self, m3u8: bool) -> Self {
+        self.m3u8 = m3u8;
+        self
+    }
+
+    /// Set whether m3u8 playlists should start with a byte order mark, which some Windows
+    /// players need to detect their encoding. Disabled by default, and ignored unless `m3u8` is
+    /// enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let downloader = Downloader::new(client, "music").m3u8(true).m3u8_bom(true);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn m3u8_bom(mut self, m3u8_bom: bool) -> Self {
+        self.m3u8_bom = m3u8_bom;
+        self
+    }
+
+    /// Set a `LoudnessAnalyzer` used to write ReplayGain tags to albums once they have been
+    /// downloaded. ReplayGain tags are only written by `download_and_tag_album` and the methods
+    /// using it, since computing the album gain requires all of its tracks. Nothing is written
+    /// when tagging is disabled with `tag`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # use std::path::{Path, PathBuf};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::downloader::{
+    ///     replaygain::{AlbumReplayGain, LoudnessAnalyzer, ReplayGain},
+    ///     Downloader,
+    /// };
+    ///
+    /// #[derive(Debug)]
+    /// struct Silent;
+    ///
+    /// impl LoudnessAnalyzer for Silent {
+    ///     fn analyze_album(
+    ///         &self,
+    ///         tracks: &[PathBuf],
+    ///     ) -> Result<AlbumReplayGain, Box<dyn std::error::Error + Send + Sync>> {
+    ///         let gain = ReplayGain { gain: 0.0, peak: 1.0 };
+    ///         Ok(AlbumReplayGain { album: gain, tracks: vec![gain; tracks.len()] })
+    ///     }
+    /// }
+    ///
+    /// let downloader = Downloader::new(client, Path::new("music")).replaygain(Silent);
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn replaygain(mut self, analyzer: impl LoudnessAnalyzer + 'static) -> Self {
+        self.loudness_analyzer = Some(Arc::new(analyzer));
+        self
+    }
+
+    /// Download and tag a track, returning the download locations of the album and track.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// let downloader = Downloader::new(client.clone(), root);
+    /// // Download "Let It Be", replacing the file if it already exists.
+    /// let track = client
+    ///     .get_track("129342731")
+    ///     .await
+    ///     .unwrap();
+    /// downloader
+    ///     .download_and_tag_track(&track, &track.album, Quality::Mp3, true)
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_track<EF1, EF2>(
+        &self,
+        track: &Track<EF1>,
+        album: &Album<EF2>,
+        quality: Quality,
+        force: bool,
+    ) -> Result<(PathBuf, PathBuf), DownloadError>
+    where
+        EF1: ExtraFlag<Album<WithoutExtra>>,
+        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
+        EF1::Extra: Sync,
+        EF2::Extra: Sync,
+    {
+        self.download_and_tag_track_with(track, album, quality, force, |_| {})
+            .await
+    }
+
+    /// Download and tag a track like `download_and_tag_track`, calling `callback` each time a
+    /// chunk of the track has been downloaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download "Let It Be", printing the progress.
+    /// let track = client
+    ///     .get_track("129342731")
+    ///     .await
+    ///     .unwrap();
+    /// downloader
+    ///     .download_and_tag_track_with(&track, &track.album, Quality::Mp3, true, |progress| {
+    ///         if let Some(fraction) = progress.fraction() {
+    ///             println!("{:.0}%", fraction * 100.0);
+    ///         }
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_track_with<EF1, EF2>(
+        &self,
+        track: &Track<EF1>,
+        album: &Album<EF2>,
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<TrackDownloadProgress>,
+    ) -> Result<(PathBuf, PathBuf), DownloadError>
+    where
+        EF1: ExtraFlag<Album<WithoutExtra>>,
+        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
+        EF1::Extra: Sync,
+        EF2::Extra: Sync,
+    {
+        let (album_path, track_path) =
+            self.get_single_track_location(track, album, &quality, !self.dry_run)?;
+        if self.dry_run {
+            return Ok((album_path, track_path));
+        }
+        let cover_raw = self.fetch_cover(album).await?;
+        let cover = audiotags::Picture::new(&cover_raw, audiotags::MimeType::Jpeg);
+        let track_path = self
+            .download_track(track, album, track_path, quality, force, cover, &callback)
+            .await?;
+        Ok((album_path, track_path))
+    }
+
+    /// Download and tag an album, returning its download location.
+    ///
+    /// If the album has no tracks, nothing is downloaded and its directory isn't created.
+    ///
+    /// # Example
+    ///
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download "Abbey Road", replacing files if they already exist.
+    /// let album = client
+    ///     .get_album("trrcz9pvaaz6b")
+    ///     .await
+    ///     .unwrap();
+    /// downloader
+    ///     .download_and_tag_album(&album, Quality::Mp3, true)
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    pub async fn download_and_tag_album(
+        &self,
+        album: &Album<WithExtra>,
+        quality: Quality,
+        force: bool,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+        self.download_and_tag_album_with(album, quality, force, |_| {})
+            .await
+    }
+
+    /// Download and tag an album like `download_and_tag_album`, calling `callback` each time a
+    /// track has been downloaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download "Abbey Road", printing the progress.
+    /// let album = client
+    ///     .get_album("trrcz9pvaaz6b")
+    ///     .await
+    ///     .unwrap();
+    /// downloader
+    ///     .download_and_tag_album_with(&album, Quality::Mp3, true, |progress| {
+    ///         match progress.track_progress.and_then(|p| p.fraction()) {
+    ///             Some(fraction) => println!(
+    ///                 "{}/{} - {:.0}%",
+    ///                 progress.downloaded_tracks + 1,
+    ///                 progress.total_tracks,
+    ///                 fraction * 100.0
+    ///             ),
+    ///             None => println!("{}/{}", progress.downloaded_tracks, progress.total_tracks),
+    ///         }
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_album_with(
+        &self,
+        album: &Album<WithExtra>,
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<AlbumDownloadProgress>,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+        let tracks: Vec<_> = album.tracks.items.iter().collect();
+        self.download_album_tracks(album, &tracks, quality, force, true, callback)
+            .await
+    }
+
+    /// Download and tag the tracks of an album whose IDs are in `track_ids`, returning the
+    /// download locations of the album and of the selected tracks.
+    ///
+    /// Tracks are filed under the album's directory and tagged with its metadata as with
+    /// `download_and_tag_album`. ReplayGain tags aren't written, since the album gain can't be
+    /// computed from a subset of its tracks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download the first two tracks of "Abbey Road".
+    /// let album = client
+    ///     .get_album("trrcz9pvaaz6b")
+    ///     .await
+    ///     .unwrap();
+    /// let track_ids: Vec<u64> = album.tracks.items.iter().take(2).map(|t| t.id).collect();
+    /// downloader
+    ///     .download_and_tag_album_tracks(&album, &track_ids, Quality::Mp3, false)
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_album_tracks(
+        &self,
+        album: &Album<WithExtra>,
+        track_ids: &[u64],
+        quality: Quality,
+        force: bool,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+        self.download_and_tag_album_tracks_with(album, track_ids, quality, force, |_| {})
+            .await
+    }
+
+    /// Download and tag the selected tracks of an album like `download_and_tag_album_tracks`,
+    /// calling `callback` each time a track has been downloaded. The progress only counts the
+    /// selected tracks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download the first two tracks of "Abbey Road", printing the progress.
+    /// let album = client
+    ///     .get_album("trrcz9pvaaz6b")
+    ///     .await
+    ///     .unwrap();
+    /// let track_ids: Vec<u64> = album.tracks.items.iter().take(2).map(|t| t.id).collect();
+    /// downloader
+    ///     .download_and_tag_album_tracks_with(&album, &track_ids, Quality::Mp3, false, |progress| {
+    ///         if progress.track_progress.is_none() {
+    ///             println!("{}/{}", progress.downloaded_tracks, progress.total_tracks);
+    ///         }
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_album_tracks_with(
+        &self,
+        album: &Album<WithExtra>,
+        track_ids: &[u64],
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<AlbumDownloadProgress>,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+        let tracks: Vec<_> = album
+            .tracks
+            .items
+            .iter()
+            .filter(|track| track_ids.contains(&track.id))
+            .collect();
+        self.download_album_tracks(album, &tracks, quality, force, false, callback)
+            .await
+    }
+
+    /// Download and tag all albums of an artist, returning their download locations.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download all albums by the Beatles, keeping existing files.
+    /// let artist = client
+    ///     .get_artist("26390")
+    ///     .await
+    ///     .unwrap();
+    /// downloader
+    ///     .download_and_tag_artist(&artist, Quality::Mp3, false)
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_artist(
+        &self,
+        artist: &Artist<WithExtra>,
+        quality: Quality,
+        force: bool,
+    ) -> Result<Vec<PathBuf>, DownloadError> {
+        self.download_and_tag_artist_with(artist, quality, force, |_| {})
+            .await
+    }
+
+    /// Download and tag all albums of an artist like `download_and_tag_artist`, calling
+    /// `callback` each time an album has been downloaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download all albums by the Beatles, printing the progress.
+    /// let artist = client
+    ///     .get_artist("26390")
+    ///     .await
+    ///     .unwrap();
+    /// downloader
+    ///     .download_and_tag_artist_with(&artist, Quality::Mp3, false, |progress| {
+    ///         println!("{}/{}", progress.downloaded_albums, progress.total_albums);
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_artist_with(
+        &self,
+        artist: &Artist<WithExtra>,
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<ArtistDownloadProgress>,
+    ) -> Result<Vec<PathBuf>, DownloadError> {
+        let albums = &artist.albums.items;
+        let total_albums = albums.len();
+        callback(&ArtistDownloadProgress {
+            downloaded_albums: 0,
+            total_albums,
+        });
+        let mut album_paths = Vec::with_capacity(total_albums);
+        for (i, album) in albums.iter().enumerate() {
+            // The artist's albums don't include their tracks, so we need to query them again.
+            let album = self.client.get_album(&album.id).await?;
+            let (album_path, _) = self
+                .download_and_tag_album(&album, quality.clone(), force)
+                .await?;
+            album_paths.push(album_path);
+            callback(&ArtistDownloadProgress {
+                downloaded_albums: i + 1,
+                total_albums,
+            });
+        }
+        Ok(album_paths)
+    }
+
+    /// Download and tag the tracks of a playlist, each in the directory of its album, returning
+    /// their download locations in playlist order, see `Playlist::ordered_tracks`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download an official Beatles playlist, keeping existing files.
+    /// let playlist = client
+    ///     .get_playlist("1141084")
+    ///     .await
+    ///     .unwrap();
+    /// downloader
+    ///     .download_and_tag_playlist(&playlist, Quality::Mp3, false)
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_playlist(
+        &self,
+        playlist: &Playlist<WithExtra>,
+        quality: Quality,
+        force: bool,
+    ) -> Result<Vec<PathBuf>, DownloadError> {
+        self.download_and_tag_playlist_with(playlist, quality, force, |_| {})
+            .await
+    }
+
+    /// Download and tag the tracks of a playlist like `download_and_tag_playlist`, calling
+    /// `callback` each time a track has been downloaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// // Download an official Beatles playlist, printing the progress.
+    /// let playlist = client
+    ///     .get_playlist("1141084")
+    ///     .await
+    ///     .unwrap();
+    /// downloader
+    ///     .download_and_tag_playlist_with(&playlist, Quality::Mp3, false, |progress| {
+    ///         if progress.track_progress.is_none() {
+    ///             println!("{}/{}", progress.downloaded_tracks, progress.total_tracks);
+    ///         }
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_and_tag_playlist_with(
+        &self,
+        playlist: &Playlist<WithExtra>,
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<PlaylistDownloadProgress>,
+    ) -> Result<Vec<PathBuf>, DownloadError> {
+        let tracks = playlist.ordered_tracks();
+        let total_tracks = tracks.len();
+        callback(&PlaylistDownloadProgress {
+            downloaded_tracks: 0,
+            total_tracks,
+            track_progress: None,
+        });
+        let mut track_paths = Vec::with_capacity(total_tracks);
+        for (i, track) in tracks.iter().enumerate() {
+            let (_, track_path) = self
+                .download_and_tag_track_with(track, &track.album, quality.clone(), force, |p| {
+                    callback(&PlaylistDownloadProgress {
+                        downloaded_tracks: i,
+                        total_tracks,
+                        track_progress: Some(*p),
+                    });
+                })
+                .await
+                .map_err(|e| e.in_track(track))?;
+            track_paths.push(track_path);
+            callback(&PlaylistDownloadProgress {
+                downloaded_tracks: i + 1,
+                total_tracks,
+                track_progress: None,
+            });
+        }
+        Ok(track_paths)
+    }
+
+    /// Download and tag any `Downloadable` item, calling `callback` with the progress of the
+    /// download. This allows downloading items of different kinds in the same way, e.g. from a
+    /// queue.
+    ///
+    /// The returned locations are the one of the track for tracks, the ones of the album
+    /// directories for albums and artists, and the ones of the tracks for playlists. Tracks are
+    /// tagged with their full album unless `fetch_full_album` is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let root = Path::new("music");
+    /// # let downloader = Downloader::new(client.clone(), root);
+    /// use qobuz::{downloader::Downloadable, link::ItemKind};
+    /// // Download "Abbey Road" and "Let It Be" (the track), printing the progress.
+    /// let queue = [(ItemKind::Album, "trrcz9pvaaz6b"), (ItemKind::Track, "129342731")];
+    /// for (kind, id) in queue {
+    ///     let item = Downloadable::fetch(&client, kind, id).await.unwrap();
+    ///     downloader
+    ///         .download(&item, Quality::Mp3, false, |progress| println!("{progress:?}"))
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// # })
+    /// ```
+    pub async fn download(
+        &self,
+        item: &Downloadable,
+        quality: Quality,
+        force: bool,
+        callback: impl ProgressCallback<DownloadProgress>,
+    ) -> Result<Vec<PathBuf>, DownloadError> {
+        match item {
+            Downloadable::Track(track) => {
+                let callback = |p: &TrackDownloadProgress| {
+                    callback(&DownloadProgress::Track(*p));
+                };
+                let (_, track_path) = if self.fetch_full_album && self.tag && !self.dry_run {
+                    let album = self.client.get_full_album_of(track).await?;
+                    self.download_and_tag_track_with(track, &album, quality, force, callback)
+                        .await?
+                } else {
+                    self.download_and_tag_track_with(track, &track.album, quality, force, callback)
+                        .await?
+                };
+                Ok(vec![track_path])
+            }
+            Downloadable::Album(album) => {
+                let (album_path, _) = self
+                    .download_and_tag_album_with(album, quality, force, |p| {
+                        callback(&DownloadProgress::Album(*p));
+                    })
+                    .await?;
+                Ok(vec![album_path])
+            }
+            Downloadable::Artist(artist) => {
+                self.download_and_tag_artist_with(artist, quality, force, |p| {
+                    callback(&DownloadProgress::Artist(*p));
+                })
+                .await
+            }
+            Downloadable::Playlist(playlist) => {
+                self.download_and_tag_playlist_with(playlist, quality, force, |p| {
+                    callback(&DownloadProgress::Playlist(*p));
+                })
+                .await
+            }
+        }
+    }
+
+    /// Download and tag the user's streamable favorite tracks, `concurrency` at a time, then
+    /// write an m3u playlist of them to `FAVORITES_M3U_FILE_NAME` in the root directory, whose
+    /// location is returned. `callback` is called with the progress of the downloads. The
+    /// playlist gets the `.m3u8` extension instead when `m3u8` is enabled.
+    ///
+    /// All tracks are attempted even if some fail, in which case the first error is returned
+    /// and the playlist isn't written. In dry-run mode, the playlist isn't written either, but
+    /// its location is still returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::path::Path;
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let downloader = Downloader::new(client, Path::new("music"));
+    /// use qobuz::downloader::m3u::PathStyle;
+    /// let m3u_path = downloader
+    ///     .download_favorite_tracks(Quality::Mp3, false, 4, PathStyle::Relative, |progress| {
+    ///         if progress.item_progress.is_none() {
+    ///             println!("{}/{}", progress.completed_items, progress.total_items);
+    ///         }
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn download_favorite_tracks(
+        &self,
+        quality: Quality,
+        force: bool,
+        concurrency: usize,
+        m3u_style: PathStyle,
+        callback: impl ProgressCallback<QueueProgress>,
+    ) -> Result<PathBuf, DownloadError> {
+        let mut queue = DownloadQueue::new(self.clone(), quality, force).concurrency(concurrency);
+        for track in self
+            .client
+            .get_user_favorites::<Track<WithExtra>>()
+            .await?
+            .into_iter()
+            .filter(|track| track.streamable)
+        {
+            queue.push(track);
+        }
+        let mut track_paths = Vec::new();
+        for paths in queue.run_with(callback).await {
+            track_paths.extend(paths?);
+        }
+        let m3u_path = self
+            .root
+            .join(FAVORITES_M3U_FILE_NAME)
+            .with_extension(self.m3u_extension());
+        if !self.dry_run {
+            self.write_playlist_m3u(&m3u_path, &track_paths, m3u_style)?;
+        }
+        Ok(m3u_path)
+    }
+
+    /// Download the tracks of a playlist that aren't in `known_track_ids`, e.g. the ones
+    /// downloaded on a previous run, then write an m3u playlist of all of its tracks in their
+    /// current order to `get_playlist_m3u_location`. This keeps a local copy of
+    /// a playlist in sync with minimal work, e.g. from a periodic job.
+    ///
+    /// Known tracks are expected at their standard location and aren't checked. The IDs of the
+    /// newly downloaded tracks are returned, to be added to the known ones for the next run.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+    /// # use std::{collections::HashSet, path::Path};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// # let downloader = Downloader::new(client, Path::new("music"));
+    /// use qobuz::downloader::m3u::PathStyle;
+    /// let mut known_track_ids = HashSet::new();
+    /// // Download an official Beatles playlist, then only the tracks added since then.
+    /// for _ in 0..2 {
+    ///     let new_track_ids = downloader
+    ///         .download_playlist_incremental("1141084", Quality::Mp3, &known_track_ids, PathStyle::Relative)
+    ///         .await
+    ///         .unwrap();
+    ///     known_track_ids.extend(new_track_ids);
+    /// }
+    /// # })
+    /// ```
+    pub async fn download_playlist_incremental(
+        &self,
+        playlist_id: &str,
+        quality: Quality,
+        known_track_ids: &HashSet<u64>,
+        m3u_style: PathStyle,
+    ) -> Result<HashSet<u64>, DownloadError> {
+        let playlist = self.client.get_playlist(playlist_id).await?;
+        let mut new_track_ids = HashSet::new();
+        let mut track_paths = Vec::with_capacity(playlist.tracks.items.len());
+        for track in playlist.ordered_tracks() {
+            let track_path = if known_track_ids.contains(&track.id) {
+                let (_, track_path) =
+                    self.get_single_track_location(track, &track.album, &quality, false)?;
+                track_path
+            } else {
+                let (_, track_path) = self
+                    .download_and_tag_track(track, &track.album, quality.clone(), false)
+                    .await
+                    .map_err(|e| e.in_track(track))?;
+                new_track_ids.insert(track.id);
+                track_path
+            };
+            track_paths.push(track_path);
+        }
+        if !self.dry_run {
+            self.write_playlist_m3u(
+                &self.get_playlist_m3u_location(&playlist)?,
+                &track_paths,
+                m3u_style,
+            )?;
+        }
+        Ok(new_track_ids)
+    }
+
+    /// Download and tag the given tracks of an album, writing ReplayGain tags if `replaygain` is
+    /// set and a `LoudnessAnalyzer` is configured.
+    async fn download_album_tracks(
+        &self,
+        album: &Album<WithExtra>,
+        items: &[&Track<WithoutExtra>],
+        quality: Quality,
+        force: bool,
+        replaygain: bool,
+        callback: impl ProgressCallback<AlbumDownloadProgress>,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DownloadError> {
+        if items.is_empty() {
+            // Don't create an empty directory for an album without tracks.
+            return Ok((self.get_standard_album_location(album, false)?, Vec::new()));
+        }
+        let final_album_path = self.get_standard_album_location(album, false)?;
+        if self.dry_run {
+            let track_paths = self.get_album_track_locations(items, &final_album_path, &quality);
+            return Ok((final_album_path, track_paths));
+        }
+        // Albums already in the root directory are visible anyway, so only new ones are staged.
+        let album_path = match &self.staging_dir {
+            Some(staging_dir) if !final_album_path.exists() => {
+                staging_dir.join(final_album_path.file_name().unwrap_or_default())
+            }
+            _ => final_album_path.clone(),
+        };
+        create_dir(&album_path)?;
+        let resume_path = album_path.join(RESUME_FILE_NAME);
+        let resume = self.resume && !force;
+        let mut track_paths = if resume {
+            read_resume_file(&resume_path, items).await?
+        } else {
+            Vec::new()
+        };
+        let resumed_tracks = track_paths.len();
+
+        let total_tracks = items.len();
+        callback(&AlbumDownloadProgress {
+            downloaded_tracks: resumed_tracks,
+            total_tracks,
+            track_progress: None,
+        });
+        if resumed_tracks < total_tracks {
+            let cover_raw = self.fetch_cover(album).await?;
+            let cover = audiotags::Picture::new(&cover_raw, audiotags::MimeType::Jpeg);
+            let locations = self.get_album_track_locations(items, &album_path, &quality);
+            let remaining = items[resumed_tracks..]
+                .iter()
+                .copied()
+                .zip(locations.into_iter().skip(resumed_tracks))
+                .enumerate();
+            let new_paths: Vec<PathBuf> = stream::iter(remaining)
+                .then(|(i, (track, track_path))| {
+                    let callback = &callback;
+                    let resume_path = &resume_path;
+                    let quality = quality.clone();
+                    let cover = cover.clone();
+                    async move {
+                        let track_path = self
+                            .download_track(track, album, track_path, quality, force, cover, &|p| {
+                                callback(&AlbumDownloadProgress {
+                                    downloaded_tracks: resumed_tracks + i,
+                                    total_tracks,
+                                    track_progress: Some(*p),
+                                });
+                            })
+                            .await
+                            .map_err(|e| e.in_track(track))?;
+                        if resume {
+                            append_resume_file(resume_path, track.id, &track_path).await?;
+                        }
+                        callback(&AlbumDownloadProgress {
+                            downloaded_tracks: resumed_tracks + i + 1,
+                            total_tracks,
+                            track_progress: None,
+                        });
+                        Ok(track_path)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<Result<_, DownloadError>>()?;
+            track_paths.extend(new_paths);
+        }
+        if resume {
+            match tokio::fs::remove_file(&resume_path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+
+        if let Some(analyzer) = self
+            .loudness_analyzer
+            .as_ref()
+            .filter(|_| replaygain && self.tag)
+        {
+            let analyzer = analyzer.clone();
+            let paths = track_paths.clone();
+            let gains = tokio::task::spawn_blocking(move || analyzer.analyze_album(&paths))
+                .await?
+                .map_err(DownloadError::LoudnessAnalysisError)?;
+            for (path, track_gain) in track_paths.iter().zip(&gains.tracks) {
+                tag_replaygain(path, track_gain, &gains.album)?;
+            }
+        }
+
+        if self.write_manifest {
+            let album_id = album.id.clone();
+            let album_path = album_path.clone();
+            let paths = track_paths.clone();
+            tokio::task::spawn_blocking(move || {
+                Manifest::new(&album_id, &album_path, &paths)?.write(&album_path)
+            })
+            .await??;
+        }
+
+        if album_path != final_album_path {
+            move_dir(&album_path, &final_album_path).await?;
+            track_paths = track_paths
+                .iter()
+                .map(|path| final_album_path.join(path.strip_prefix(&album_path).unwrap_or(path)))
+                .collect();
+        }
+
+        Ok((final_album_path, track_paths))
+    }
+
+    /// Download a track to a temporary file next to its final location, tag it, then move it
+    /// into place. This way, concurrent downloads of the same track never see a partially
+    /// written or tagged file.
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(skip_all, fields(track_id = track.id, quality = %quality))]
+    async fn download_track<EF1, EF2>(
+        &self,
+        track: &Track<EF1>,
+        album: &Album<EF2>,
+        track_path: PathBuf,
+        quality: Quality,
+        force: bool,
+        cover: audiotags::Picture<'_>,
+        callback: &impl ProgressCallback<TrackDownloadProgress>,
+    ) -> Result<PathBuf, DownloadError>
+    where
+        EF1: ExtraFlag<Album<WithoutExtra>>,
+        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
+        EF1::Extra: Sync,
+    {
+        self.check_cancelled()?;
+        if !force {
+            let processed_path = self
+                .post_processor
+                .as_ref()
+                .and_then(|post_processor| post_processor.output_path(&track_path));
+            let existing = match processed_path {
+                // Processed files can't be compared with the ones served by Qobuz.
+                Some(processed_path) => processed_path.exists().then_some(processed_path),
+                None => (track_path.exists()
+                    && (!self.verify_existing
+                        || self
+                            .is_complete(&track_path, track.id, quality.clone())
+                            .await?))
+                    .then(|| track_path.clone()),
+            };
+            if let Some(existing) = existing {
+                let size = tokio::fs::metadata(&existing).await?.len();
+                callback(&TrackDownloadProgress {
+                    downloaded: size,
+                    total: Some(size),
+                    done: true,
+                });
+                return Ok(existing);
+            }
+        }
+        if let Some(reason) = track.unavailability_reason(Utc::now()) {
+            return Err(DownloadError::Unavailable(reason));
+        }
+        let temp_path = get_temporary_location(&track_path);
+        let res: Result<_, DownloadError> = async {
+            let progress = self
+                .download_to(&temp_path, track.id, quality, callback)
+                .await?;
+            if self.tag {
+                match &self.tagger {
+                    Some(tagger) => {
+                        tagger
+                            .tag(
+                                &track.without_extra(),
+                                &album.without_extra(),
+                                cover.data,
+                                &temp_path,
+                            )
+                            .await?;
+                    }
+                    None => tag_track(track, &temp_path, album, cover)?,
+                }
+                if let Some(audio_info) = track.audio_info.filter(|_| self.qobuz_replaygain) {
+                    tag_track_replaygain(&temp_path, &audio_info.into())?;
+                }
+            }
+            std::fs::rename(&temp_path, &track_path)?;
+            Ok(progress)
+        }
+        .await;
+        if res.is_err() {
+            // The temporary file may not exist, in which case there is nothing to clean up.
+            let _ = tokio::fs::remove_file(&temp_path).await;
+        }
+        let progress = res?;
+        let track_path = match &self.post_processor {
+            Some(post_processor) => post_processor.process(&track_path).await?,
+            None => track_path,
+        };
+        callback(&TrackDownloadProgress {
+            done: true,
+            ..progress
+        });
+        Ok(track_path)
+    }
+
+    /// Fetch the cover of `album` to tag its tracks, or nothing when tagging is disabled.
+    async fn fetch_cover<EF>(&self, album: &Album<EF>) -> Result<bytes::Bytes, DownloadError>
+    where
+        EF: ExtraFlag<Array<Track<WithoutExtra>>>,
+    {
+        if !self.tag {
+            return Ok(bytes::Bytes::new());
+        }
+        Ok(reqwest::get(album.image.large.clone())
+            .await?
+            .bytes()
+            .await?)
+    }
+
+    async fn download_to(
+        &self,
+        path: &Path,
+        track_id: u64,
+        quality: Quality,
+        callback: &impl ProgressCallback<TrackDownloadProgress>,
+    ) -> Result<TrackDownloadProgress, DownloadError> {
+        let mut out = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .await?;
+        // Subscriptions without access to the requested quality get the best allowed one, which
+        // is also the one the extension of `path` was chosen for.
+        let quality = self.client.clamp_quality(quality);
+        let file = self
+            .client
+            .get_streamable_track_file(track_id, quality.clone())
+            .await?;
+        match file.availability(&quality) {
+            Availability::Unavailable(reason) => return Err(DownloadError::Unavailable(reason)),
+            Availability::Downgraded {
+                quality: delivered, ..
+            } => tracing::info!(
+                track_id,
+                requested = %quality,
+                %delivered,
+                "track is only available in a lower quality"
+            ),
+            Availability::Available => {}
+        }
+        // Lower qualities are accepted as long as the file format matches the extension.
+        if FileExtension::from(&file.quality) != FileExtension::from(&quality) {
+            return Err(ApiError::QualityUnavailable {
+                requested: quality,
+                delivered: file.quality,
+            }
+            .into());
+        }
+        let res = self
+            .client
+            .reqwest_client
+            .get(file.url)
+            .send()
+            .await?
+            .error_for_status()?;
+        let expected_md5 = if self.verify_downloads && self.verify_etag_md5 {
+            get_etag_md5(&res)
+        } else {
+            None
+        };
+        let start = Instant::now();
+        let mut md5_context = md5::Context::new();
+        let mut progress = TrackDownloadProgress {
+            downloaded: 0,
+            total: res.content_length(),
+            done: false,
+        };
+        callback(&progress);
+        let mut bytes_stream =
+            with_idle_timeout(res.bytes_stream(), self.client.stream_idle_timeout);
+        loop {
+            let item = match &self.cancellation_token {
+                Some(token) => tokio::select! {
+                    () = token.cancelled() => return Err(DownloadError::Cancelled),
+                    item = bytes_stream.next() => item,
+                },
+                None => bytes_stream.next().await,
+            };
+            let Some(item) = item else {
+                break;
+            };
+            let item = item?;
+            tokio::io::copy(&mut item.as_ref(), &mut out).await?;
+            if expected_md5.is_some() {
+                md5_context.consume(&item);
+            }
+            progress.downloaded += item.len() as u64;
+            callback(&progress);
+        }
+        out.flush().await?;
+        tracing::info!(
+            track_id,
+            path = %path.display(),
+            bytes = progress.downloaded,
+            duration_ms = start.elapsed().as_millis(),
+            "downloaded track"
+        );
+        if self.verify_downloads {
+            if let Some(total) = progress.total.filter(|&t| t != progress.downloaded) {
+                return Err(DownloadError::IntegrityMismatch {
+                    expected: format!("{total} bytes"),
+                    actual: format!("{} bytes", progress.downloaded),
+                });
+            }
+            if let Some(expected_md5) = expected_md5 {
+                let actual_md5 = format!("{:x}", md5_context.compute());
+                if actual_md5 != expected_md5 {
+                    return Err(DownloadError::IntegrityMismatch {
+                        expected: format!("MD5 {expected_md5}"),
+                        actual: format!("MD5 {actual_md5}"),
+                    });
+                }
+            }
+        }
+        Ok(progress)
+    }
+
+    /// Check whether the file at `path` is at least as large as the file served by Qobuz.
+    async fn is_complete(
+        &self,
+        path: &Path,
+        track_id: u64,
+        quality: Quality,
+    ) -> Result<bool, DownloadError> {
+        let file = self.client.get_track_file(track_id, quality).await?;
+        let expected = self
+            .client
+            .reqwest_client
+            .head(file.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .content_length();
+        let actual = tokio::fs::metadata(path).await?.len();
+        Ok(expected.is_none_or(|expected| actual >= expected))
+    }
+
+    // TODO: configurable path format
+    pub fn get_standard_album_location<EF>(
+        &self,
+        album: &Album<EF>,
+        ensure_exists: bool,
+    ) -> Result<PathBuf, DownloadError>
+    where
+        EF: ExtraFlag<Array<Track<WithoutExtra>>>,
+    {
+        let mut path = self.root.to_path_buf();
+        path.push(format!(
+            "{} - {}",
+            sanitize_filename(&album.artist.name),
+            sanitize_filename(&album.title),
+        ));
+        if ensure_exists {
+            create_dir(&path)?;
+        }
+        Ok(path)
+    }
+
+    /// Get the locations of the tracks of an album in `album_path`, in the same order.
+    ///
+    /// Tracks sharing a title, e.g. untitled or hidden tracks, would get the same standard
+    /// location and overwrite each other, so their track number, and disc number on multi-disc
+    /// albums, is appended to their file name.
+    fn get_album_track_locations(
+        &self,
+        tracks: &[&Track<WithoutExtra>],
+        album_path: &Path,
+        quality: &Quality,
+    ) -> Vec<PathBuf> {
+        let locations: Vec<PathBuf> = tracks
+            .iter()
+            .map(|track| self.get_standard_track_location(track, album_path, quality))
+            .collect();
+        let mut counts: HashMap<&Path, usize> = HashMap::new();
+        for location in &locations {
+            *counts.entry(location).or_default() += 1;
+        }
+        let multi_disc = tracks
+            .iter()
+            .any(|track| track.media_number != tracks[0].media_number);
+        tracks
+            .iter()
+            .zip(&locations)
+            .map(|(track, location)| {
+                if counts[location.as_path()] == 1 {
+                    return location.clone();
+                }
+                let number = if multi_disc {
+                    format!("{}-{}", track.media_number, track.track_number)
+                } else {
+                    track.track_number.to_string()
+                };
+                tracing::warn!(
+                    track_id = track.id,
+                    path = %location.display(),
+                    "Several tracks of the album have the same location, appending {number}"
+                );
+                album_path.join(format!(
+                    "{} ({number}).{}",
+                    sanitize_filename(&track.file_title()),
+                    self.file_extension(quality)
+                ))
+            })
+            .collect()
+    }
+
+    /// Get the directory and location of a track downloaded on its own, according to the
+    /// `SingleLayout`.
+    ///
+    /// Outside of album directories, the album, and the artist when they are all in the same
+    /// directory, are part of the file name, so that tracks with the same title from different
+    /// albums don't get the same location.
+    fn get_single_track_location<EF1, EF2>(
+        &self,
+        track: &Track<EF1>,
+        album: &Album<EF2>,
+        quality: &Quality,
+        ensure_exists: bool,
+    ) -> Result<(PathBuf, PathBuf), DownloadError>
+    where
+        EF1: ExtraFlag<Album<WithoutExtra>>,
+        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
+    {
+        let (path, name_parts) = match self.single_layout {
+            SingleLayout::AlbumDir => {
+                let path = self.get_standard_album_location(album, ensure_exists)?;
+                let track_path = self.get_standard_track_location(track, &path, quality);
+                return Ok((path, track_path));
+            }
+            SingleLayout::ArtistDir => (
+                self.root.join(sanitize_filename(&album.artist.name)),
+                vec![album.title.as_str()],
+            ),
+            SingleLayout::Flat => (
+                self.root.to_path_buf(),
+                vec![album.artist.name.as_str(), album.title.as_str()],
+            ),
+        };
+        if ensure_exists {
+            create_dir(&path)?;
+        }
+        let title = track.file_title();
+        let name = name_parts
+            .into_iter()
+            .chain([title.as_str()])
+            .map(sanitize_filename)
+            .collect::<Vec<_>>()
+            .join(" - ");
+        let track_path = path.join(format!("{name}.{}", self.file_extension(quality)));
+        Ok((path, track_path))
+    }
+
+    /// Get the location of the m3u file of a playlist in the root directory, named according
+    /// to `playlist_m3u_format`.
+    ///
+    /// # Errors
+    ///
+    /// If the format gives an empty file name for this playlist, e.g. `{name}` for a playlist
+    /// without a name.
+    pub fn get_playlist_m3u_location<EF>(
+        &self,
+        playlist: &Playlist<EF>,
+    ) -> Result<PathBuf, DownloadError>
+    where
+        EF: ExtraFlag<Array<Track<WithExtra>>>,
+    {
+        let name = fill_placeholders(
+            &self.playlist_m3u_format,
+            &[
+                ("name", sanitize_filename(&playlist.name)),
+                ("owner", sanitize_filename(&playlist.owner.name)),
+                ("id", playlist.id.to_string()),
+                ("date", playlist.created_at.date_naive().to_string()),
+            ],
+        );
+        let name = sanitize_filename(&name);
+        if name.is_empty() {
+            return Err(DownloadError::EmptyPlaylistM3uName(
+                self.playlist_m3u_format.clone(),
+            ));
+        }
+        Ok(self.root.join(format!("{name}.{}", self.m3u_extension())))
+    }
+
+    /// The extension of the playlists written by the downloader.
+    const fn m3u_extension(&self) -> &'static str {
+        if self.m3u8 {
+            "m3u8"
+        } else {
+            "m3u"
+        }
+    }
+
+    /// Write a playlist as m3u or m3u8 according to the `m3u8` and `m3u8_bom` options.
+    fn write_playlist_m3u(
+        &self,
+        m3u_path: &Path,
+        track_paths: &[PathBuf],
+        style: PathStyle,
+    ) -> Result<(), std::io::Error> {
+        if self.m3u8 {
+            write_m3u8(m3u_path, track_paths, style, self.m3u8_bom)
+        } else {
+            write_m3u(m3u_path, track_paths, style)
+        }
+    }
+
+    #[must_use]
+    pub fn get_standard_track_location<EF>(
+        &self,
+        track: &Track<EF>,
+        album_path: &Path,
+        quality: &Quality,
+    ) -> PathBuf
+    where
+        EF: ExtraFlag<Album<WithoutExtra>>,
+    {
+        // The title may contain dots, so don't use `set_extension`, which would replace the end
+        // of the title.
+        album_path.join(format!(
+            "{}.{}",
+            sanitize_filename(&track.file_title()),
+            self.file_extension(quality)
+        ))
+    }
+
+    /// The extension of tracks downloaded in `quality`, or in the best quality allowed by the
+    /// subscription if it is lower.
+    fn file_extension(&self, quality: &Quality) -> FileExtension {
+        FileExtension::from(&self.client.clamp_quality(quality.clone()))
+    }
+}
+
+/// A callback receiving progress updates of type `P` during a download.
+pub trait ProgressCallback<P>: Fn(&P) + Send + Sync {}
+
+impl<P, F> ProgressCallback<P> for F where F: Fn(&P) + Send + Sync {}
+
+/// Where tracks downloaded on their own are placed, see `Downloader::single_layout`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SingleLayout {
+    /// In the directory of their album, as when downloading the whole album.
+    #[default]
+    AlbumDir,
+    /// In a directory named after the album artist, with the album in the file name, e.g.
+    /// `root/Artist/Album - Track.flac`.
+    ArtistDir,
+    /// Directly in the root directory, with the artist and album in the file name, e.g.
+    /// `root/Artist - Album - Track.flac`.
+    Flat,
+}
+
+/// Progress of a track download, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackDownloadProgress {
+    pub downloaded: u64,
+    /// The size of the track, if known.
+    pub total: Option<u64>,
+    /// Whether the track is done, i.e. downloaded, tagged and in place. This is only set in the
+    /// last progress update of a successful download, which is always sent, even when the track
+    /// was already downloaded.
+    pub done: bool,
+}
+
+impl TrackDownloadProgress {
+    /// The downloaded fraction of the track, between 0 and 1, if its size is known.
+    #[must_use]
+    pub fn fraction(&self) -> Option<f64> {
+        #[allow(clippy::cast_precision_loss)]
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| self.downloaded as f64 / total as f64)
+    }
+}
+
+/// Progress of an album download, in tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlbumDownloadProgress {
+    pub downloaded_tracks: usize,
+    pub total_tracks: usize,
+    /// The progress of the track being downloaded, or `None` in the updates sent when starting
+    /// and each time a track has been downloaded.
+    pub track_progress: Option<TrackDownloadProgress>,
+}
+
+/// Progress of an artist download, in albums.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtistDownloadProgress {
+    pub downloaded_albums: usize,
+    pub total_albums: usize,
+}
+
+/// Progress of a playlist download, in tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaylistDownloadProgress {
+    pub downloaded_tracks: usize,
+    pub total_tracks: usize,
+    /// The progress of the track being downloaded, or `None` in the updates sent when starting
+    /// and each time a track has been downloaded.
+    pub track_progress: Option<TrackDownloadProgress>,
+}
+
+/// Progress of the download of a `Downloadable` item, see `Downloader::download`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadProgress {
+    Track(TrackDownloadProgress),
+    Album(AlbumDownloadProgress),
+    Artist(ArtistDownloadProgress),
+    Playlist(PlaylistDownloadProgress),
+}
+
+/// Any item that can be downloaded with `Downloader::download`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Downloadable {
+    Track(Box<Track<WithExtra>>),
+    Album(Box<Album<WithExtra>>),
+    Artist(Box<Artist<WithExtra>>),
+    Playlist(Box<Playlist<WithExtra>>),
+}
+
+impl Downloadable {
+    /// Get the item of the given kind with the given ID, e.g. as returned by
+    /// `link::parse_url`.
+    pub async fn fetch(client: &crate::Client, kind: ItemKind, id: &str) -> Result<Self, ApiError> {
+        Ok(match kind {
+            ItemKind::Track => Self::Track(Box::new(client.get_track(id).await?)),
+            ItemKind::Album => Self::Album(Box::new(client.get_album(id).await?)),
+            ItemKind::Artist => Self::Artist(Box::new(client.get_artist(id).await?)),
+            ItemKind::Playlist => Self::Playlist(Box::new(client.get_playlist(id).await?)),
+        })
+    }
+}
+
+impl From<Track<WithExtra>> for Downloadable {
+    fn from(track: Track<WithExtra>) -> Self {
+        Self::Track(Box::new(track))
+    }
+}
+
+impl From<Album<WithExtra>> for Downloadable {
+    fn from(album: Album<WithExtra>) -> Self {
+        Self::Album(Box::new(album))
+    }
+}
+
+impl From<Artist<WithExtra>> for Downloadable {
+    fn from(artist: Artist<WithExtra>) -> Self {
+        Self::Artist(Box::new(artist))
+    }
+}
+
+impl From<Playlist<WithExtra>> for Downloadable {
+    fn from(playlist: Playlist<WithExtra>) -> Self {
+        Self::Playlist(Box::new(playlist))
+    }
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DownloadError {
+    #[error("tagging error `{0}`")]
+    TaggingError(#[from] TaggingError),
+    #[error("IO error `{0}`")]
+    IoError(#[from] std::io::Error),
+    #[error("couldn't create directory `{}`: {source}", path.display())]
+    CreateDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("reqwest error `{0}`")]
     ReqwestError(#[from] reqwest::Error),
     #[error("API error `{0}`")]
     ApiError(#[from] ApiError),
+    #[error("post-processing error `{0}`")]
+    PostProcessingError(#[from] PostProcessingError),
+    #[error("loudness analysis error `{0}`")]
+    LoudnessAnalysisError(Box<dyn std::error::Error + Send + Sync>),
+    #[error("task error `{0}`")]
+    JoinError(#[from] tokio::task::JoinError),
+    #[error("downloaded file is corrupt: expected {expected}, got {actual}")]
+    IntegrityMismatch { expected: String, actual: String },
+    #[error("track is unavailable: {0}")]
+    Unavailable(UnavailabilityReason),
+    #[error("download was cancelled")]
+    Cancelled,
+    #[error("playlist m3u format `{0}` gives an empty file name")]
+    EmptyPlaylistM3uName(String),
+    /// A failure while downloading a track of an album or playlist.
+    #[error("couldn't download track {id} `{title}`")]
+    Track {
+        id: u64,
+        title: String,
+        source: Box<DownloadError>,
+    },
+}
+
+impl DownloadError {
+    /// Attach the track the error happened on, unless it already has one or is a cancellation,
+    /// which isn't specific to a track.
+    fn in_track<EF>(self, track: &Track<EF>) -> Self
+    where
+        EF: ExtraFlag<Album<WithoutExtra>>,
+    {
+        match self {
+            Self::Cancelled | Self::Track { .. } => self,
+            source => Self::Track {
+                id: track.id,
+                title: track.full_title(),
+                source: Box::new(source),
+            },
+        }
+    }
+}
+
+/// Get the MD5 hash of a response's body from its `ETag` header, if it is one. Other kinds of
+/// `ETag`s, e.g. those of multipart uploads, can't be used to verify the body.
+fn get_etag_md5(res: &reqwest::Response) -> Option<String> {
+    let etag = res.headers().get(reqwest::header::ETAG)?.to_str().ok()?;
+    let etag = etag.trim_start_matches("W/").trim_matches('"');
+    (etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit())).then(|| etag.to_lowercase())
+}
+
+/// Get a unique temporary location next to `path` to download a track to. The extension is kept
+/// so that the file can be tagged before being moved to `path`.
+fn get_temporary_location(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let n = COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+    let mut file_name = OsString::from(".");
+    file_name.push(path.file_stem().unwrap_or_default());
+    file_name.push(format!(".part-{}-{n}", std::process::id()));
+    let mut temp_path = path.with_file_name(file_name);
+    if let Some(extension) = path.extension() {
+        temp_path.set_extension(extension);
+    }
+    temp_path
+}
+
+/// Create the directory at `path` and its parents if they don't exist.
+fn create_dir(path: &Path) -> Result<(), DownloadError> {
+    if path.is_dir() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(path).map_err(|source| DownloadError::CreateDir {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Move the directory at `from` to `to`, copying it then removing it if they are on different
+/// filesystems.
+async fn move_dir(from: &Path, to: &Path) -> Result<(), DownloadError> {
+    if let Some(parent) = to.parent() {
+        create_dir(parent)?;
+    }
+    match tokio::fs::rename(from, to).await {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            let (from, to) = (from.to_path_buf(), to.to_path_buf());
+            tokio::task::spawn_blocking(move || {
+                copy_dir(&from, &to)?;
+                std::fs::remove_dir_all(&from)
+            })
+            .await??;
+            Ok(())
+        }
+        res => Ok(res?),
+    }
+}
+
+/// Recursively copy the directory at `from` to `to`.
+fn copy_dir(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace the `{key}` placeholders of `format` with their values in a single pass, so that
+/// placeholders appearing in the values themselves are kept as is. Unknown placeholders are
+/// left untouched.
+fn fill_placeholders(format: &str, values: &[(&str, String)]) -> String {
+    let mut filled = String::with_capacity(format.len());
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(key, _)| *key == &rest[1..end])
+                .map(|(_, value)| (value, end))
+        });
+        if let Some((value, end)) = value {
+            filled.push_str(value);
+            rest = &rest[end + 1..];
+        } else {
+            filled.push('{');
+            rest = &rest[1..];
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// Default name of playlist m3u files, see `Downloader::playlist_m3u_format`.
+pub const DEFAULT_PLAYLIST_M3U_FORMAT: &str = "{name}";
+
+/// Name of the playlist written by `Downloader::download_favorite_tracks` in the root directory.
+pub const FAVORITES_M3U_FILE_NAME: &str = "favorites.m3u";
+
+/// Name of the file recording the tracks downloaded so far in an album directory when resuming
+/// downloads is enabled, see `Downloader::resume`.
+pub const RESUME_FILE_NAME: &str = ".qobuz-resume";
+
+/// Read the locations of the leading tracks of `items` recorded in the resume file at `path`.
+///
+/// Each line of the file holds the ID of a downloaded track and its location, separated by a tab.
+async fn read_resume_file(
+    path: &Path,
+    items: &[&Track<WithoutExtra>],
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let recorded: HashMap<u64, PathBuf> = content
+        .lines()
+        .filter_map(|line| {
+            let (id, track_path) = line.split_once('\t')?;
+            Some((id.parse().ok()?, PathBuf::from(track_path)))
+        })
+        .collect();
+    Ok(items
+        .iter()
+        .map_while(|track| recorded.get(&track.id).filter(|p| p.exists()).cloned())
+        .collect())
+}
+
+/// Record a downloaded track in the resume file at `path`, see `read_resume_file`.
+async fn append_resume_file(
+    path: &Path,
+    track_id: u64,
+    track_path: &Path,
+) -> Result<(), std::io::Error> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    let line = format!("{track_id}\t{}\n", track_path.display());
+    file.write_all(line.as_bytes()).await
 }
 
 #[must_use]
 pub fn sanitize_filename(filename: &str) -> String {
-    let filename = filename.trim().replace('/', "-");
+    // Metadata strings may be in any normalization form, and some systems (e.g. macOS) use NFD
+    // for filenames, so use NFC everywhere to get the same paths on all systems.
+    let filename: String = filename.trim().replace('/', "-").nfc().collect();
     filename.trim_start_matches('.').to_string()
 }
 
@@ -246,7 +2136,7 @@ pub fn sanitize_filename(filename: &str) -> String {
 mod tests {
     #![allow(clippy::unwrap_used)]
     use super::*;
-    use crate::test_utils::make_client_and_downloader;
+    use crate::test_utils::{fixture, make_client_and_downloader};
     use tokio::test;
 
     const HIRES192_TRACK: &str = "18893849"; // Creedence Clearwater Revival - Lodi
@@ -257,6 +2147,101 @@ mod tests {
         Quality::HiRes192,
     ];
 
+    #[test]
+    async fn test_staging_dir() {
+        let (client, _) = make_client_and_downloader().await;
+        let tmp = std::env::temp_dir().join(format!("qobuz-staging-{}", std::process::id()));
+        let (root, staging) = (tmp.join("music"), tmp.join("staging"));
+        let downloader = Downloader::new(client.clone(), &root).staging_dir(&staging);
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let (album_path, track_paths) = downloader
+            .download_and_tag_album(&album, Quality::Mp3, false)
+            .await
+            .unwrap();
+        let staged = staging.read_dir().unwrap().count();
+        let all_exist = track_paths
+            .iter()
+            .all(|path| path.starts_with(&album_path) && path.exists());
+        std::fs::remove_dir_all(&tmp).unwrap();
+        assert!(album_path.starts_with(&root));
+        assert!(all_exist);
+        assert_eq!(staged, 0);
+    }
+
+    #[test]
+    async fn test_cancellation_token() {
+        let (client, _) = make_client_and_downloader().await;
+        // A root of its own, since other tests download the same album to the shared one.
+        let root = std::env::temp_dir().join(format!("qobuz-cancellation-{}", std::process::id()));
+        let token = CancellationToken::new();
+        let downloader = Downloader::new(client.clone(), &root).cancellation_token(token.clone());
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        token.cancel();
+        let res = downloader
+            .download_and_tag_album(&album, Quality::Mp3, true)
+            .await;
+        let album_path = downloader
+            .get_standard_album_location(&album, false)
+            .unwrap();
+        let has_part_files = album_path.is_dir()
+            && std::fs::read_dir(&album_path).unwrap().any(|entry| {
+                entry
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .contains(".part-")
+            });
+        if root.exists() {
+            std::fs::remove_dir_all(&root).unwrap();
+        }
+        assert!(matches!(res, Err(DownloadError::Cancelled)));
+        assert!(!has_part_files);
+    }
+
+    #[test]
+    async fn test_create_dir_error() {
+        let (client, _) = make_client_and_downloader().await;
+        let root = std::env::temp_dir().join(format!("qobuz-create-dir-{}", std::process::id()));
+        // A file where the root directory should be, so that it can't be created.
+        std::fs::write(&root, b"").unwrap();
+        let downloader = Downloader::new(client.clone(), &root);
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let res = downloader.get_standard_album_location(&album, true);
+        std::fs::remove_file(&root).unwrap();
+        assert!(matches!(
+            res,
+            Err(DownloadError::CreateDir { path, .. }) if path.starts_with(&root)
+        ));
+    }
+
+    #[test]
+    async fn test_track_location_with_work_and_version() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let mut track = client.get_track("129342731").await.unwrap();
+        track.title = "I. Allegro con brio".to_string();
+        track.work = Some("Symphony No. 5 in C Minor, Op. 67".to_string());
+        track.version = Some("Remastered 2009".to_string());
+        let path = downloader.get_standard_track_location(&track, Path::new("album"), &Quality::Cd);
+        assert_eq!(
+            path,
+            Path::new(
+                "album/Symphony No. 5 in C Minor, Op. 67 - I. Allegro con brio - Remastered 2009.flac"
+            )
+        );
+
+        track.work = None;
+        track.version = None;
+        let path = downloader.get_standard_track_location(&track, Path::new("album"), &Quality::Cd);
+        assert_eq!(path, Path::new("album/I. Allegro con brio.flac"));
+    }
+
+    #[test]
+    async fn test_sanitize_filename() {
+        // "e" followed by a combining acute accent
+        assert_eq!(sanitize_filename("Beyonce\u{301}"), "Beyonc\u{e9}");
+        assert_eq!(sanitize_filename(" ..AC/DC "), "AC-DC");
+    }
+
     #[test]
     async fn test_download_and_tag_track() {
         let (client, downloader) = make_client_and_downloader().await;
@@ -269,6 +2254,530 @@ mod tests {
         }
     }
 
+    #[test]
+    async fn test_download_and_tag_track_with() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let track = client.get_track(HIRES192_TRACK).await.unwrap();
+        let last = std::sync::Mutex::new(None);
+        downloader
+            .download_and_tag_track_with(&track, &track.album, Quality::Mp3, true, |p| {
+                *last.lock().unwrap() = Some(*p);
+            })
+            .await
+            .unwrap();
+        let last = last.into_inner().unwrap().unwrap();
+        assert_eq!(Some(last.downloaded), last.total);
+        assert!(last.done);
+
+        // The done signal is also sent when the track is already there.
+        let last = std::sync::Mutex::new(None);
+        downloader
+            .download_and_tag_track_with(&track, &track.album, Quality::Mp3, false, |p| {
+                *last.lock().unwrap() = Some(*p);
+            })
+            .await
+            .unwrap();
+        assert!(last.into_inner().unwrap().unwrap().done);
+    }
+
+    #[test]
+    async fn test_tagger() {
+        #[derive(Debug, Default)]
+        struct RecordingTagger(std::sync::Mutex<Vec<u64>>);
+        impl Tagger for RecordingTagger {
+            fn tag<'a>(
+                &'a self,
+                track: &'a Track<WithoutExtra>,
+                _album: &'a Album<WithoutExtra>,
+                cover: &'a [u8],
+                path: &'a Path,
+            ) -> futures::future::BoxFuture<'a, Result<(), TaggingError>> {
+                Box::pin(async move {
+                    assert!(!cover.is_empty());
+                    assert!(path.exists());
+                    self.0.lock().unwrap().push(track.id);
+                    Ok(())
+                })
+            }
+        }
+
+        let client = crate::test_utils::make_client().await;
+        let tagger = Arc::new(RecordingTagger::default());
+        let mut downloader = Downloader::new(client.clone(), Path::new("music/tagger"));
+        downloader.tagger = Some(tagger.clone());
+        let track = client.get_track("129342731").await.unwrap();
+        downloader
+            .download_and_tag_track(&track, &track.album, Quality::Mp3, true)
+            .await
+            .unwrap();
+        assert_eq!(*tagger.0.lock().unwrap(), vec![track.id]);
+    }
+
+    #[test]
+    async fn test_no_tag() {
+        let client = crate::test_utils::make_client().await;
+        let downloader = Downloader::new(client.clone(), Path::new("music/no-tag")).tag(false);
+        let track = client.get_track("129342731").await.unwrap();
+        let (_, track_path) = downloader
+            .download_and_tag_track(&track, &track.album, Quality::Mp3, true)
+            .await
+            .unwrap();
+        assert!(id3::Tag::read_from_path(track_path).is_err());
+    }
+
+    #[test]
+    async fn test_skip_processed_track() {
+        let client = crate::test_utils::make_client().await;
+        let root = Path::new("music/processed");
+        let downloader = Downloader::new(client.clone(), root).post_processor(
+            post_processing::ExternalCommand::new("false", ["{input}", "{output}"], "opus"),
+        );
+        let track = client.get_track("129342731").await.unwrap();
+        let (_, track_path) = downloader
+            .get_single_track_location(&track, &track.album, &Quality::Mp3, true)
+            .unwrap();
+        let processed_path = track_path.with_extension("opus");
+        std::fs::write(&processed_path, b"processed").unwrap();
+        // The command would fail if the track was downloaded and processed again.
+        let (_, track_path) = downloader
+            .download_and_tag_track(&track, &track.album, Quality::Mp3, false)
+            .await
+            .unwrap();
+        assert_eq!(track_path, processed_path);
+        assert_eq!(std::fs::read(&track_path).unwrap(), b"processed");
+    }
+
+    #[test]
+    async fn test_qobuz_replaygain() {
+        let client = crate::test_utils::make_client().await;
+        let downloader = Downloader::new(client.clone(), Path::new("music/qobuz-replaygain"))
+            .qobuz_replaygain(true);
+        let mut track = client.get_track("129342731").await.unwrap();
+        track.audio_info = Some(crate::types::AudioInfo {
+            replaygain_track_gain: -7.5,
+            replaygain_track_peak: 0.98,
+        });
+        let (_, track_path) = downloader
+            .download_and_tag_track(&track, &track.album, Quality::Mp3, true)
+            .await
+            .unwrap();
+        let tag = id3::Tag::read_from_path(track_path).unwrap();
+        let gain = tag
+            .extended_texts()
+            .find(|t| t.description == "REPLAYGAIN_TRACK_GAIN")
+            .unwrap();
+        assert_eq!(gain.value, "-7.50 dB");
+    }
+
+    #[test]
+    async fn test_single_layout() {
+        let client = crate::test_utils::make_client().await;
+        let track = client.get_track("129342731").await.unwrap();
+        let root = Path::new("music/single-layout");
+        for (layout, expected) in [
+            (
+                SingleLayout::AlbumDir,
+                root.join(format!(
+                    "{} - {}",
+                    sanitize_filename(&track.album.artist.name),
+                    sanitize_filename(&track.album.title)
+                )),
+            ),
+            (
+                SingleLayout::ArtistDir,
+                root.join(sanitize_filename(&track.album.artist.name)),
+            ),
+            (SingleLayout::Flat, root.to_path_buf()),
+        ] {
+            let downloader = Downloader::new(client.clone(), root)
+                .single_layout(layout)
+                .dry_run(true);
+            let (album_path, track_path) = downloader
+                .download_and_tag_track(&track, &track.album, Quality::Mp3, false)
+                .await
+                .unwrap();
+            assert_eq!(album_path, expected);
+            assert_eq!(track_path.parent(), Some(expected.as_path()));
+        }
+    }
+
+    #[test]
+    async fn test_single_track_locations_with_same_titles() {
+        let client = crate::Client::public("000000000");
+        let track: Track<WithExtra> = fixture("track");
+        let mut other = track.clone();
+        other.id += 1;
+        other.album.id = "otheralbum".to_string();
+        other.album.title = "Other Album".to_string();
+        for layout in [
+            SingleLayout::AlbumDir,
+            SingleLayout::ArtistDir,
+            SingleLayout::Flat,
+        ] {
+            let downloader = Downloader::new(client.clone(), "music").single_layout(layout);
+            let location = |track: &Track<WithExtra>| {
+                downloader
+                    .get_single_track_location(track, &track.album, &Quality::Mp3, false)
+                    .unwrap()
+                    .1
+            };
+            assert_ne!(location(&track), location(&other), "{layout:?}");
+        }
+        let downloader = Downloader::new(client, "music").single_layout(SingleLayout::Flat);
+        let (_, track_path) = downloader
+            .get_single_track_location(&other, &other.album, &Quality::Mp3, false)
+            .unwrap();
+        assert_eq!(
+            track_path,
+            Path::new("music").join(format!(
+                "{} - Other Album - {}.mp3",
+                sanitize_filename(&other.album.artist.name),
+                sanitize_filename(&other.title)
+            ))
+        );
+    }
+
+    #[test]
+    async fn test_album_track_locations_with_same_titles() {
+        let downloader = Downloader::new(crate::Client::public("000000000"), Path::new("music"));
+        let album: Album<WithExtra> = fixture("album");
+        let mut tracks = album.tracks.items.clone();
+        for track in &mut tracks {
+            track.title = "Untitled".to_string();
+            track.work = None;
+            track.version = None;
+        }
+        let tracks: Vec<_> = tracks.iter().collect();
+        let album_path = Path::new("music/album");
+        let locations = downloader.get_album_track_locations(&tracks, album_path, &Quality::Mp3);
+        assert_eq!(
+            locations,
+            [
+                album_path.join(format!("Untitled ({}).mp3", tracks[0].track_number)),
+                album_path.join(format!("Untitled ({}).mp3", tracks[1].track_number)),
+            ]
+        );
+
+        // Tracks with distinct titles keep their standard location.
+        let tracks: Vec<_> = album.tracks.items.iter().collect();
+        let locations = downloader.get_album_track_locations(&tracks, album_path, &Quality::Mp3);
+        assert_eq!(
+            locations[0],
+            downloader.get_standard_track_location(tracks[0], album_path, &Quality::Mp3)
+        );
+    }
+
+    #[test]
+    async fn test_download_playlist_incremental() {
+        let client = crate::test_utils::make_client().await;
+        let root = Path::new("music/incremental");
+        let downloader = Downloader::new(client.clone(), root);
+        let playlist = client.get_playlist("1141084").await.unwrap();
+        let tracks = &playlist.tracks.items;
+        let known_track_ids: HashSet<u64> = tracks[1..].iter().map(|track| track.id).collect();
+        let new_track_ids = downloader
+            .download_playlist_incremental(
+                "1141084",
+                Quality::Mp3,
+                &known_track_ids,
+                PathStyle::Relative,
+            )
+            .await
+            .unwrap();
+        assert_eq!(new_track_ids, HashSet::from([tracks[0].id]));
+        let m3u = std::fs::read_to_string(
+            root.join(format!("{}.m3u", sanitize_filename(&playlist.name))),
+        )
+        .unwrap();
+        assert_eq!(m3u.lines().count(), tracks.len());
+    }
+
+    #[test]
+    async fn test_album_track_progress() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let updates = std::sync::Mutex::new(Vec::new());
+        downloader
+            .download_and_tag_album_with(&album, Quality::Mp3, true, |progress| {
+                updates.lock().unwrap().push(*progress);
+            })
+            .await
+            .unwrap();
+        let updates = updates.into_inner().unwrap();
+        assert!(updates
+            .iter()
+            .any(|p| p.downloaded_tracks == 0 && p.track_progress.is_some_and(|t| t.done)));
+        let last = updates.last().unwrap();
+        assert_eq!(last.downloaded_tracks, last.total_tracks);
+        assert_eq!(last.track_progress, None);
+    }
+
+    #[test]
+    async fn test_error_in_track() {
+        let album: Album<WithExtra> = fixture("album");
+        let track = &album.tracks.items[0];
+        let error = DownloadError::IntegrityMismatch {
+            expected: "a".to_string(),
+            actual: "b".to_string(),
+        }
+        .in_track(track)
+        .in_track(&album.tracks.items[1]);
+        assert!(matches!(
+            &error,
+            DownloadError::Track { id, source, .. }
+                if *id == track.id && matches!(**source, DownloadError::IntegrityMismatch { .. })
+        ));
+        assert!(error.to_string().contains(&track.title));
+        // The cause is only available as the source, so it isn't printed twice in error chains.
+        assert!(!error.to_string().contains("corrupt"));
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.to_string().contains("corrupt"));
+        assert!(matches!(
+            DownloadError::Cancelled.in_track(track),
+            DownloadError::Cancelled
+        ));
+    }
+
+    #[test]
+    async fn test_playlist_m3u_location() {
+        let playlist: Playlist<WithExtra> = fixture("playlist");
+        let downloader = Downloader::new(crate::Client::public("000000000"), "music");
+        assert_eq!(
+            downloader.get_playlist_m3u_location(&playlist).unwrap(),
+            Path::new("music").join(format!("{}.m3u", sanitize_filename(&playlist.name)))
+        );
+        assert_eq!(
+            downloader
+                .clone()
+                .m3u8(true)
+                .get_playlist_m3u_location(&playlist)
+                .unwrap()
+                .extension(),
+            Some("m3u8".as_ref())
+        );
+        let downloader = downloader.playlist_m3u_format("{owner}/{name} ({id}, {date})");
+        assert_eq!(
+            downloader.get_playlist_m3u_location(&playlist).unwrap(),
+            Path::new("music").join(format!(
+                "{}-{} ({}, {}).m3u",
+                playlist.owner.name,
+                playlist.name,
+                playlist.id,
+                playlist.created_at.date_naive()
+            ))
+        );
+        // Placeholders in the values themselves aren't replaced.
+        let mut named = playlist.clone();
+        named.name = "{id}".to_string();
+        assert_eq!(
+            downloader
+                .clone()
+                .playlist_m3u_format("{name} {unknown}")
+                .get_playlist_m3u_location(&named)
+                .unwrap(),
+            Path::new("music").join("{id} {unknown}.m3u")
+        );
+        named.name = " ".to_string();
+        assert!(matches!(
+            downloader
+                .playlist_m3u_format("{name}")
+                .get_playlist_m3u_location(&named),
+            Err(DownloadError::EmptyPlaylistM3uName(_))
+        ));
+    }
+
+    #[test]
+    async fn test_concurrent_downloads_of_same_album() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let tracks = &album.tracks.items;
+        let (a, b, c) = tokio::join!(
+            downloader.download_and_tag_track(&tracks[0], &album, Quality::Mp3, true),
+            downloader.download_and_tag_track(&tracks[0], &album, Quality::Mp3, true),
+            downloader.download_and_tag_track(&tracks[1], &album, Quality::Mp3, true),
+        );
+        assert_eq!(a.unwrap(), b.unwrap());
+        c.unwrap();
+    }
+
+    #[test]
+    async fn test_fetch_full_album() {
+        #[derive(Debug, Default)]
+        struct AlbumRecordingTagger(std::sync::Mutex<Vec<Option<u64>>>);
+        impl Tagger for AlbumRecordingTagger {
+            fn tag<'a>(
+                &'a self,
+                _track: &'a Track<WithoutExtra>,
+                album: &'a Album<WithoutExtra>,
+                _cover: &'a [u8],
+                _path: &'a Path,
+            ) -> futures::future::BoxFuture<'a, Result<(), TaggingError>> {
+                Box::pin(async move {
+                    self.0.lock().unwrap().push(album.tracks_count);
+                    Ok(())
+                })
+            }
+        }
+
+        let client = crate::test_utils::make_client().await;
+        let item = Downloadable::fetch(&client, ItemKind::Track, "129342731")
+            .await
+            .unwrap();
+        let Downloadable::Track(track) = &item else {
+            panic!("expected a track");
+        };
+        let full_album = client.get_full_album_of(track).await.unwrap();
+        for fetch_full_album in [true, false] {
+            let tagger = Arc::new(AlbumRecordingTagger::default());
+            let mut downloader = Downloader::new(client.clone(), "music/full-album")
+                .fetch_full_album(fetch_full_album);
+            downloader.tagger = Some(tagger.clone());
+            downloader
+                .download(&item, Quality::Mp3, true, |_| {})
+                .await
+                .unwrap();
+            let expected = if fetch_full_album {
+                full_album.tracks_count
+            } else {
+                track.album.tracks_count
+            };
+            assert_eq!(*tagger.0.lock().unwrap(), vec![expected]);
+        }
+        assert!(full_album.tracks_count.is_some());
+    }
+
+    #[test]
+    async fn test_dry_run() {
+        let client = crate::test_utils::make_client().await;
+        let downloader = Downloader::new(client.clone(), Path::new("music/dry-run")).dry_run(true);
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let (album_path, track_paths) = downloader
+            .download_and_tag_album(&album, Quality::Mp3, true)
+            .await
+            .unwrap();
+        assert_eq!(track_paths.len(), album.tracks.items.len());
+        assert!(!album_path.exists());
+    }
+
+    #[test]
+    async fn test_download_and_tag_empty_album() {
+        let client = crate::test_utils::make_client().await;
+        let downloader = Downloader::new(client.clone(), Path::new("music/empty"));
+        let mut album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        album.tracks.items.clear();
+        let (album_path, track_paths) = downloader
+            .download_and_tag_album(&album, Quality::Mp3, true)
+            .await
+            .unwrap();
+        assert!(track_paths.is_empty());
+        assert!(!album_path.exists());
+    }
+
+    #[test]
+    async fn test_download_and_tag_album_tracks() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let track_ids: Vec<u64> = album.tracks.items.iter().take(2).map(|t| t.id).collect();
+        let (album_path, track_paths) = downloader
+            .download_and_tag_album_tracks(&album, &track_ids, Quality::Mp3, true)
+            .await
+            .unwrap();
+        assert_eq!(track_paths.len(), track_ids.len());
+        assert!(track_paths.iter().all(|p| p.starts_with(&album_path)));
+    }
+
+    #[test]
+    async fn test_resume_album_download() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let downloader = downloader.resume(true);
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let (album_path, track_paths) = downloader
+            .download_and_tag_album(&album, Quality::Mp3, false)
+            .await
+            .unwrap();
+        assert!(!album_path.join(RESUME_FILE_NAME).exists());
+        // Pretend the download was interrupted after the first track.
+        let first = &album.tracks.items[0];
+        append_resume_file(
+            &album_path.join(RESUME_FILE_NAME),
+            first.id,
+            &track_paths[0],
+        )
+        .await
+        .unwrap();
+        let first_progress = std::sync::Mutex::new(None);
+        let (_, resumed_paths) = downloader
+            .download_and_tag_album_with(&album, Quality::Mp3, false, |progress| {
+                first_progress
+                    .lock()
+                    .unwrap()
+                    .get_or_insert(progress.downloaded_tracks);
+            })
+            .await
+            .unwrap();
+        assert_eq!(*first_progress.lock().unwrap(), Some(1));
+        assert_eq!(resumed_paths, track_paths);
+        assert!(!album_path.join(RESUME_FILE_NAME).exists());
+    }
+
+    #[test]
+    async fn test_download() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let item = Downloadable::fetch(&client, ItemKind::Track, HIRES192_TRACK)
+            .await
+            .unwrap();
+        let updates = std::sync::Mutex::new(Vec::new());
+        let paths = downloader
+            .download(&item, Quality::Mp3, true, |progress| {
+                updates.lock().unwrap().push(*progress);
+            })
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 1);
+        assert!(updates
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|p| matches!(p, DownloadProgress::Track(_))));
+    }
+
+    #[test]
+    async fn test_download_queue() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let mut queue = queue::DownloadQueue::new(downloader, Quality::Mp3, true).concurrency(2);
+        queue.push(client.get_track(HIRES192_TRACK).await.unwrap());
+        queue.push(client.get_album("lz75qrx8pnjac").await.unwrap());
+        let last = std::sync::Mutex::new(None);
+        let results = queue
+            .run_with(|progress| *last.lock().unwrap() = Some(*progress))
+            .await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_ok));
+        let last = last.lock().unwrap().unwrap();
+        assert_eq!((last.completed_items, last.total_items), (2, 2));
+    }
+
+    #[test]
+    async fn test_write_manifest() {
+        let (client, downloader) = make_client_and_downloader().await;
+        let downloader = downloader.write_manifest(true);
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let (album_path, track_paths) = downloader
+            .download_and_tag_album(&album, Quality::Mp3, false)
+            .await
+            .unwrap();
+        let manifest: Manifest = serde_json::from_reader(
+            std::fs::File::open(album_path.join(manifest::MANIFEST_FILE_NAME)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest.album_id, album.id);
+        assert_eq!(manifest.tracks.len(), track_paths.len());
+        for (entry, path) in manifest.tracks.iter().zip(&track_paths) {
+            assert_eq!(album_path.join(&entry.file), *path);
+            assert_eq!(entry.size, std::fs::metadata(path).unwrap().len());
+        }
+    }
+
     #[test]
     async fn test_download_and_tag_album() {
         let (client, downloader) = make_client_and_downloader().await;
@@ -0,0 +1,108 @@
+use futures::future::BoxFuture;
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use tokio::process::Command;
+
+/// A step run on each track after it has been downloaded and tagged, e.g. to transcode it.
+///
+/// This crate doesn't bundle any encoder: implementations are expected to call external tools,
+/// as `ExternalCommand` does.
+pub trait PostProcessor: Debug + Send + Sync {
+    /// Process the track at `path`, returning its location once processed.
+    fn process<'a>(&'a self, path: &'a Path)
+        -> BoxFuture<'a, Result<PathBuf, PostProcessingError>>;
+
+    /// The location the track at `path` will have once processed, if known in advance. It is
+    /// used to skip tracks that have already been processed, which are otherwise downloaded
+    /// again since their original file is gone.
+    fn output_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// A `PostProcessor` running an external command, e.g. `ffmpeg`, to convert tracks to another
+/// format.
+///
+/// The `{input}` and `{output}` placeholders in the arguments are replaced with the path of the
+/// downloaded track and the path of the converted track, which has the given `extension`. The
+/// downloaded track is removed once the command has succeeded.
+///
+/// # Example
+///
+/// ```
+/// use qobuz::downloader::post_processing::ExternalCommand;
+/// // Convert tracks to MP3 V0
+/// let command = ExternalCommand::new(
+///     "ffmpeg",
+///     ["-i", "{input}", "-q:a", "0", "{output}"],
+///     "mp3",
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExternalCommand {
+    program: String,
+    args: Vec<String>,
+    extension: String,
+}
+
+impl ExternalCommand {
+    #[must_use]
+    pub fn new(
+        program: impl Into<String>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+        extension: impl Into<String>,
+    ) -> Self {
+        Self {
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            extension: extension.into(),
+        }
+    }
+}
+
+impl PostProcessor for ExternalCommand {
+    fn process<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> BoxFuture<'a, Result<PathBuf, PostProcessingError>> {
+        Box::pin(async move {
+            let output = path.with_extension(&self.extension);
+            if output == path {
+                return Err(PostProcessingError::SameExtension);
+            }
+            let input_str = path.to_string_lossy();
+            let output_str = output.to_string_lossy();
+            let status = Command::new(&self.program)
+                .args(self.args.iter().map(|arg| {
+                    arg.replace("{input}", &input_str)
+                        .replace("{output}", &output_str)
+                }))
+                .status()
+                .await?;
+            if !status.success() {
+                return Err(PostProcessingError::CommandFailed(status));
+            }
+            tokio::fs::remove_file(path).await?;
+            Ok(output)
+        })
+    }
+    fn output_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(path.with_extension(&self.extension))
+    }
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PostProcessingError {
+    #[error("IO error `{0}`")]
+    IoError(#[from] std::io::Error),
+    #[error("command failed with `{0}`")]
+    CommandFailed(std::process::ExitStatus),
+    #[error("the output extension is the same as the input's")]
+    SameExtension,
+    #[error("post-processing error `{0}`")]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
@@ -0,0 +1,135 @@
+use super::{DownloadError, DownloadProgress, Downloadable, Downloader, ProgressCallback};
+use crate::quality::Quality;
+use futures::{stream, StreamExt};
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A queue of `Downloadable` items downloaded concurrently, reporting their progress to a single
+/// callback.
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # use qobuz::{auth::Credentials, Client, downloader::Downloader, quality::Quality};
+/// # use std::path::Path;
+/// # let credentials = Credentials::from_env().unwrap();
+/// # let client = Client::new(credentials).await.unwrap();
+/// # let downloader = Downloader::new(client.clone(), Path::new("music"));
+/// use qobuz::downloader::queue::DownloadQueue;
+/// // Download "Abbey Road" and "Let It Be" (the track), two items at a time.
+/// let mut queue = DownloadQueue::new(downloader, Quality::Mp3, false).concurrency(2);
+/// queue.push(client.get_album("trrcz9pvaaz6b").await.unwrap());
+/// queue.push(client.get_track("129342731").await.unwrap());
+/// let results = queue
+///     .run_with(|progress| {
+///         println!("{}/{} items", progress.completed_items, progress.total_items);
+///     })
+///     .await;
+/// # })
+/// ```
+#[derive(Debug, Clone)]
+pub struct DownloadQueue {
+    downloader: Downloader,
+    quality: Quality,
+    force: bool,
+    concurrency: usize,
+    items: Vec<Downloadable>,
+}
+
+impl DownloadQueue {
+    /// Create an empty queue downloading items with the given `Downloader`, in the given
+    /// quality, replacing existing files if `force` is set.
+    #[must_use]
+    pub const fn new(downloader: Downloader, quality: Quality, force: bool) -> Self {
+        Self {
+            downloader,
+            quality,
+            force,
+            concurrency: 1,
+            items: Vec::new(),
+        }
+    }
+
+    /// Set how many items are downloaded at the same time, which defaults to 1.
+    #[must_use]
+    pub const fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Add an item at the end of the queue.
+    pub fn push(&mut self, item: impl Into<Downloadable>) {
+        self.items.push(item.into());
+    }
+
+    /// The number of items in the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Download all items, returning the result of `Downloader::download` for each of them, in
+    /// the order of the queue. A failed download doesn't stop the other ones.
+    pub async fn run(self) -> Vec<Result<Vec<PathBuf>, DownloadError>> {
+        self.run_with(|_| {}).await
+    }
+
+    /// Download all items like `run`, calling `callback` with the progress of the queue each
+    /// time an item progresses or is done.
+    pub async fn run_with(
+        self,
+        callback: impl ProgressCallback<QueueProgress>,
+    ) -> Vec<Result<Vec<PathBuf>, DownloadError>> {
+        let total_items = self.items.len();
+        let completed_items = AtomicUsize::new(0);
+        let callback = &callback;
+        let completed_items = &completed_items;
+        let downloader = &self.downloader;
+        let quality = &self.quality;
+        let force = self.force;
+        stream::iter(self.items.iter().enumerate())
+            .map(|(item, downloadable)| async move {
+                let res = downloader
+                    .download(downloadable, quality.clone(), force, |progress| {
+                        callback(&QueueProgress {
+                            completed_items: completed_items.load(Ordering::Relaxed),
+                            total_items,
+                            item,
+                            item_progress: Some(*progress),
+                        });
+                    })
+                    .await;
+                callback(&QueueProgress {
+                    completed_items: completed_items.fetch_add(1, Ordering::Relaxed) + 1,
+                    total_items,
+                    item,
+                    item_progress: None,
+                });
+                res
+            })
+            .buffered(self.concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+/// Progress of a `DownloadQueue`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueProgress {
+    /// The number of items done, whether they succeeded or not.
+    pub completed_items: usize,
+    pub total_items: usize,
+    /// The index in the queue of the item this update is about.
+    pub item: usize,
+    /// The progress of the item, or `None` if the item is done.
+    pub item_progress: Option<DownloadProgress>,
+}
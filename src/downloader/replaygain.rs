@@ -0,0 +1,40 @@
+use crate::types::AudioInfo;
+use std::{error::Error, fmt::Debug, path::PathBuf};
+
+/// ReplayGain values of a track or an album.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayGain {
+    /// The gain to apply, in dB.
+    pub gain: f64,
+    /// The peak amplitude, where 1.0 is full scale.
+    pub peak: f64,
+}
+
+impl From<AudioInfo> for ReplayGain {
+    fn from(audio_info: AudioInfo) -> Self {
+        Self {
+            gain: audio_info.replaygain_track_gain,
+            peak: audio_info.replaygain_track_peak,
+        }
+    }
+}
+
+/// ReplayGain values of an album and of each of its tracks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlbumReplayGain {
+    pub album: ReplayGain,
+    /// The values of each track, in the same order as the analyzed tracks.
+    pub tracks: Vec<ReplayGain>,
+}
+
+/// An analyzer computing the loudness of downloaded albums, e.g. with EBU R128.
+///
+/// This crate doesn't bundle any audio decoder, so the analysis is left to implementations.
+/// Since analysis is expensive, it is run with `tokio::task::spawn_blocking`.
+pub trait LoudnessAnalyzer: Debug + Send + Sync {
+    /// Analyze the tracks of an album, returning their ReplayGain values and the album's.
+    fn analyze_album(
+        &self,
+        tracks: &[PathBuf],
+    ) -> Result<AlbumReplayGain, Box<dyn Error + Send + Sync>>;
+}
@@ -1,12 +1,50 @@
+use super::replaygain::ReplayGain;
 use crate::types::{
     extra::{ExtraFlag, WithoutExtra},
     Album, Array, Track,
 };
 use chrono::{Datelike, NaiveDate};
-use id3::frame::Timestamp;
-use std::path::Path;
+use futures::future::BoxFuture;
+use id3::{
+    frame::{ExtendedText, Timestamp},
+    TagLike,
+};
+use std::{fmt::Debug, path::Path};
 use thiserror::Error;
 
+/// Writes the tags of each downloaded track, before it is moved to its final location and post
+/// processed. See `Downloader::tagger`.
+pub trait Tagger: Debug + Send + Sync {
+    /// Tag the track at `path`, `cover` being the JPEG cover of its album.
+    fn tag<'a>(
+        &'a self,
+        track: &'a Track<WithoutExtra>,
+        album: &'a Album<WithoutExtra>,
+        cover: &'a [u8],
+        path: &'a Path,
+    ) -> BoxFuture<'a, Result<(), TaggingError>>;
+}
+
+/// The built-in `Tagger`, writing the title, artists, album, track and disc numbers, date, genre
+/// and cover of tracks with `tag_track`. Used when no other tagger is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioTagsTagger;
+
+impl Tagger for AudioTagsTagger {
+    fn tag<'a>(
+        &'a self,
+        track: &'a Track<WithoutExtra>,
+        album: &'a Album<WithoutExtra>,
+        cover: &'a [u8],
+        path: &'a Path,
+    ) -> BoxFuture<'a, Result<(), TaggingError>> {
+        Box::pin(async move {
+            let cover = audiotags::Picture::new(cover, audiotags::MimeType::Jpeg);
+            tag_track(track, path, album, cover)
+        })
+    }
+}
+
 pub fn tag_track<EF1, EF2>(
     track: &Track<EF1>,
     path: &Path,
@@ -30,26 +68,167 @@ where
             }
         },
     };
-    tag.set_title(&track.title);
-    tag.set_date(datetime_to_timestamp(track.release_date_original)?);
-    tag.set_year(track.release_date_original.year());
+    let fields = TagFields::new(track, album)?;
+    tag.set_title(&fields.title);
+    tag.set_artist(fields.artist);
     tag.set_album(audiotags::Album {
-        title: &album.title,
-        artist: Some(&album.artist.name),
+        title: fields.album.title,
+        artist: Some(fields.album.artist),
         cover: Some(album_cover),
     });
-    tag.set_disc((
-        track.media_number.try_into()?,
-        album.media_count.try_into()?,
-    ));
-    tag.set_track_number(track.track_number.try_into()?);
-    tag.set_artist(&album.artist.name);
-    tag.set_genre(&album.genre.name);
+    tag.set_date(datetime_to_timestamp(fields.album.date)?);
+    tag.set_year(fields.album.date.year());
+    tag.set_genre(fields.album.genre);
+    tag.set_disc((fields.disc_number, fields.album.total_discs));
+    match fields.album.total_tracks {
+        Some(total_tracks) => tag.set_track((fields.track_number, total_tracks)),
+        None => tag.set_track_number(fields.track_number),
+    }
 
     tag.write_to_path(path)?;
+    if let Some(version) = track.version.as_deref().filter(|v| !v.is_empty()) {
+        tag_version(path, version)?;
+    }
+    Ok(())
+}
+
+/// Write the version of a track, e.g. "Remastered 2009", to its own tag, which `audiotags`
+/// doesn't support: the ID3 subtitle (`TIT3`) frame for MP3 files and the `VERSION` Vorbis
+/// comment for FLAC files.
+fn tag_version(path: &Path, version: &str) -> Result<(), TaggingError> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("mp3") => {
+            let mut tag = id3::Tag::read_from_path(path)?;
+            tag.set_text("TIT3", version);
+            tag.write_to_path(path, id3::Version::Id3v24)?;
+        }
+        Some("flac") => {
+            let mut tag = metaflac::Tag::read_from_path(path)?;
+            tag.set_vorbis("VERSION", vec![version]);
+            tag.write_to_path(path)?;
+        }
+        // The version is still part of the title tag.
+        _ => {}
+    }
     Ok(())
 }
 
+/// Write the ReplayGain tags of a track, using ID3 `TXXX` frames for MP3 files and Vorbis comments
+/// for FLAC files.
+pub fn tag_replaygain(
+    path: &Path,
+    track_gain: &ReplayGain,
+    album_gain: &ReplayGain,
+) -> Result<(), TaggingError> {
+    write_replaygain_tags(
+        path,
+        [
+            (
+                "REPLAYGAIN_TRACK_GAIN",
+                format!("{:.2} dB", track_gain.gain),
+            ),
+            ("REPLAYGAIN_TRACK_PEAK", format!("{:.6}", track_gain.peak)),
+            (
+                "REPLAYGAIN_ALBUM_GAIN",
+                format!("{:.2} dB", album_gain.gain),
+            ),
+            ("REPLAYGAIN_ALBUM_PEAK", format!("{:.6}", album_gain.peak)),
+        ],
+    )
+}
+
+/// Write the ReplayGain track tags only, e.g. from the values computed by Qobuz.
+pub fn tag_track_replaygain(path: &Path, track_gain: &ReplayGain) -> Result<(), TaggingError> {
+    write_replaygain_tags(
+        path,
+        [
+            (
+                "REPLAYGAIN_TRACK_GAIN",
+                format!("{:.2} dB", track_gain.gain),
+            ),
+            ("REPLAYGAIN_TRACK_PEAK", format!("{:.6}", track_gain.peak)),
+        ],
+    )
+}
+
+fn write_replaygain_tags(
+    path: &Path,
+    tags: impl IntoIterator<Item = (&'static str, String)>,
+) -> Result<(), TaggingError> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("mp3") => {
+            let mut tag = id3::Tag::read_from_path(path)?;
+            for (description, value) in tags {
+                tag.add_frame(ExtendedText {
+                    description: description.to_string(),
+                    value,
+                });
+            }
+            tag.write_to_path(path, id3::Version::Id3v24)?;
+        }
+        Some("flac") => {
+            let mut tag = metaflac::Tag::read_from_path(path)?;
+            for (key, value) in tags {
+                tag.set_vorbis(key, vec![value]);
+            }
+            tag.write_to_path(path)?;
+        }
+        _ => return Err(TaggingError::UnsupportedFormat(path.to_path_buf())),
+    }
+    Ok(())
+}
+
+/// The tag fields of a track.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TagFields<'a> {
+    album: AlbumTagFields<'a>,
+    title: String,
+    /// The performer of the track, which may differ from the album artist, e.g. on compilations.
+    artist: &'a str,
+    track_number: u16,
+    disc_number: u16,
+}
+
+/// The tag fields derived from the album only, so that they are identical for all its tracks
+/// and players see them as a single album, e.g. for gapless playback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AlbumTagFields<'a> {
+    title: &'a str,
+    artist: &'a str,
+    /// The release date of the album, used instead of the ones of its tracks, which differ on
+    /// compilations.
+    date: NaiveDate,
+    genre: &'a str,
+    total_discs: u16,
+    total_tracks: Option<u16>,
+}
+
+impl<'a> TagFields<'a> {
+    fn new<EF1, EF2>(track: &'a Track<EF1>, album: &'a Album<EF2>) -> Result<Self, TaggingError>
+    where
+        EF1: ExtraFlag<Album<WithoutExtra>>,
+        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
+    {
+        Ok(Self {
+            album: AlbumTagFields {
+                title: &album.title,
+                artist: &album.artist.name,
+                date: album.release_date_original,
+                genre: &album.genre.name,
+                total_discs: album.media_count.try_into()?,
+                total_tracks: album.tracks_count.map(u16::try_from).transpose()?,
+            },
+            title: track.full_title(),
+            artist: track
+                .performer
+                .as_ref()
+                .map_or(&album.artist.name, |performer| &performer.name),
+            track_number: track.track_number.try_into()?,
+            disc_number: track.media_number.try_into()?,
+        })
+    }
+}
+
 fn datetime_to_timestamp(dt: NaiveDate) -> Result<Timestamp, std::num::TryFromIntError> {
     Ok(Timestamp {
         day: Some(dt.day0().try_into()?),
@@ -62,6 +241,7 @@ fn datetime_to_timestamp(dt: NaiveDate) -> Result<Timestamp, std::num::TryFromIn
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum TaggingError {
     #[error("couldn't cast int type `{0}`")]
     TryFromIntError(#[from] std::num::TryFromIntError),
@@ -69,4 +249,42 @@ pub enum TaggingError {
     AudioTags(#[from] audiotags::Error),
     #[error("IO error `{0}`")]
     IoError(#[from] std::io::Error),
+    #[error("id3 error `{0}`")]
+    Id3(#[from] id3::Error),
+    #[error("metaflac error `{0}`")]
+    Metaflac(#[from] metaflac::Error),
+    #[error("unsupported file format for `{0}`")]
+    UnsupportedFormat(std::path::PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::{
+        test_utils::fixture,
+        types::{extra::WithExtra, Performer},
+    };
+
+    #[test]
+    fn test_album_fields_are_shared() {
+        let album: Album<WithExtra> = fixture("album");
+        let first = &album.tracks.items[0];
+        // Tracks of compilations have their own performers and release dates.
+        let mut second = album.tracks.items[1].clone();
+        second.performer = Some(Performer {
+            id: 1,
+            name: "Guest Artist".to_string(),
+        });
+        second.release_date_original = NaiveDate::from_ymd_opt(1999, 1, 1).unwrap();
+
+        let first_fields = TagFields::new(first, &album).unwrap();
+        let second_fields = TagFields::new(&second, &album).unwrap();
+        assert_eq!(first_fields.album, second_fields.album);
+        assert_eq!(first_fields.album.date, album.release_date_original);
+        assert_eq!(first_fields.album.total_tracks, Some(2));
+        assert_ne!(first_fields.title, second_fields.title);
+        assert_eq!(second_fields.artist, "Guest Artist");
+        assert_eq!(second_fields.track_number, 2);
+    }
 }
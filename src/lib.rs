@@ -1,34 +1,72 @@
 pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod downloader;
+pub mod link;
+#[cfg(feature = "test-util")]
+pub mod mock;
 pub mod quality;
+pub mod sync;
 pub mod types;
 
+mod cache;
 #[cfg(test)]
 mod test_utils;
 
 use crate::{
-    auth::{get_user_auth_token, Credentials, LoginError},
+    auth::{login, Credentials, LoginError},
+    cache::{ResponseCache, TrackFileCache},
     quality::Quality,
     types::{
-        extra::{RootEntity, WithExtra, WithoutExtra},
+        extra::{ExtraFlag, RootEntity, WithExtra, WithoutExtra},
         traits::Favoritable,
-        Album, Array, Artist, Playlist, QobuzType, Track,
+        Album, Array, Artist, Availability, CoverSize, Credit, Playlist, QobuzType, ReleaseType,
+        SizeEstimate, Track, TrackFile, UserInfo,
     },
 };
 use bytes::Bytes;
-use futures::Stream;
+use chrono::{DateTime, Utc};
+use core::fmt::Display;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
 use serde::de::DeserializeOwned;
 use serde_json::Value;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 
-const API_URL: &str = "https://www.qobuz.com/api.json/0.2/";
+/// Base URL of the Qobuz API, see `HttpOptions::base_url`.
+pub const API_URL: &str = "https://www.qobuz.com/api.json/0.2/";
 const API_USER_AGENT: &str =
     "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:83.0) Gecko/20100101 Firefox/83.0";
+/// Default timeout of API requests, see `Client::with_request_timeout`.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default idle timeout of track streams, see `Client::with_stream_idle_timeout`.
+pub const DEFAULT_STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default number of retries of `Client::get_streamable_track_file` when a sample is served, see
+/// `Client::with_sample_retries`.
+pub const DEFAULT_SAMPLE_RETRIES: u32 = 1;
+/// Number of items queried per request on paginated endpoints.
+const PAGE_SIZE: i64 = 500;
+/// Maximum number of requests sent at the same time when fetching multiple items.
+const MAX_CONCURRENT_REQUESTS: usize = 8;
 
 #[derive(Debug, Clone)]
 pub struct Client {
     pub reqwest_client: reqwest::Client,
+    base_url: String,
     secret: String,
+    user: Value,
+    max_quality: Option<Quality>,
+    default_quality: Option<Quality>,
+    request_timeout: Duration,
+    stream_idle_timeout: Duration,
+    track_file_cache: Option<Arc<Mutex<TrackFileCache>>>,
+    response_cache: Option<Arc<Mutex<ResponseCache>>>,
+    sample_retries: u32,
+    authenticated: bool,
 }
 
 impl Client {
@@ -45,68 +83,251 @@ impl Client {
     /// # })
     /// ```
     pub async fn new(credentials: Credentials) -> Result<Self, LoginError> {
-        let uat = get_user_auth_token(&credentials).await?;
-        let reqwest_client = make_http_client(&credentials.app_id, Some(&uat));
+        Self::new_with_http_options(credentials, &HttpOptions::default()).await
+    }
+
+    /// Create a new `Client` like `new`, sending all requests, including the login request, with
+    /// the given `HttpOptions`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use qobuz::{auth::Credentials, Client, HttpOptions};
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let options = HttpOptions::default()
+    ///     .user_agent("my-app/1.0")
+    ///     .header(
+    ///         reqwest::header::ACCEPT_LANGUAGE,
+    ///         reqwest::header::HeaderValue::from_static("en-US"),
+    ///     );
+    /// let client = Client::new_with_http_options(credentials, &options)
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn new_with_http_options(
+        credentials: Credentials,
+        options: &HttpOptions,
+    ) -> Result<Self, LoginError> {
+        let (uat, user) = login(&credentials, options).await?;
+        let reqwest_client = make_http_client(&credentials.app_id, Some(&uat), options);
+        let max_quality = serde_json::from_value::<UserInfo>(user.clone())
+            .ok()
+            .and_then(|user| user.max_quality());
 
         Ok(Self {
             reqwest_client,
+            base_url: options.api_url().to_string(),
             secret: credentials.secret,
+            user,
+            max_quality,
+            default_quality: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            stream_idle_timeout: DEFAULT_STREAM_IDLE_TIMEOUT,
+            track_file_cache: None,
+            response_cache: None,
+            sample_retries: DEFAULT_SAMPLE_RETRIES,
+            authenticated: true,
         })
     }
 
-    /// Get the download URL of a track.
+    /// Create a `Client` without logging in, sending only the app ID with requests.
+    ///
+    /// Such a client can only be used to get catalog metadata, e.g. with `get_album`,
+    /// `get_artist` or the search methods, for example to browse before the user logs in.
+    /// Methods needing a logged-in user, like getting track files or favorites, return
+    /// `ApiError::NotAuthenticated`.
     ///
     /// # Example
     ///
     /// ```
     /// # tokio_test::block_on(async {
-    /// # use qobuz::{auth::Credentials, Client};
-    /// # let credentials = Credentials::from_env().unwrap();
-    /// # let client = Client::new(credentials).await.unwrap();
-    /// use qobuz::quality::Quality;
-    /// // Get download URL of "Let it Be" (the track)
-    /// let track = client
-    ///     .get_track_file_url("129342731", Quality::HiRes96)
-    ///     .await
-    ///     .unwrap();
+    /// use qobuz::{auth::Credentials, Client};
+    /// let app_id = Credentials::from_env().unwrap().app_id;
+    /// let client = Client::public(&app_id);
+    /// // Get information on "Abbey Road"
+    /// let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
     /// # })
     /// ```
-    pub async fn get_track_file_url(
-        &self,
-        track_id: &str, // TODO: u64?
-        quality: Quality,
-    ) -> Result<url::Url, ApiError> {
-        let timestamp_now = chrono::Utc::now().timestamp().to_string();
+    #[must_use]
+    pub fn public(app_id: &str) -> Self {
+        Self::public_with_http_options(app_id, &HttpOptions::default())
+    }
 
-        let quality_id: u8 = quality.into();
+    /// Create a `Client` without logging in like `public`, with the given options for HTTP
+    /// requests, e.g. to send them to another base URL.
+    #[must_use]
+    pub fn public_with_http_options(app_id: &str, options: &HttpOptions) -> Self {
+        Self {
+            reqwest_client: make_http_client(app_id, None, options),
+            base_url: options.api_url().to_string(),
+            secret: String::new(),
+            user: Value::Null,
+            max_quality: None,
+            default_quality: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            stream_idle_timeout: DEFAULT_STREAM_IDLE_TIMEOUT,
+            track_file_cache: None,
+            response_cache: None,
+            sample_retries: DEFAULT_SAMPLE_RETRIES,
+            authenticated: false,
+        }
+    }
 
-        let r_sig_hash = format!(
-            "{:x}",
-            md5::compute(format!(
-                "trackgetFileUrlformat_id{}intentstreamtrack_id{}{}{}",
-                quality_id, track_id, timestamp_now, self.secret
-            ))
-        );
+    /// Whether this client is logged in, i.e. wasn't created with `public`.
+    #[must_use]
+    pub const fn is_authenticated(&self) -> bool {
+        self.authenticated
+    }
 
-        let params = [
-            ("request_ts", timestamp_now.as_str()),
-            ("request_sig", &r_sig_hash),
-            ("track_id", track_id),
-            ("format_id", &quality_id.to_string()),
-            ("intent", "stream"),
-        ];
-        let res: Value = self.do_request("track/getFileUrl", &params).await?;
-        if res.get("sample") == Some(&Value::Bool(true)) {
-            return Err(ApiError::IsSample);
+    /// Fail with `ApiError::NotAuthenticated` if this client isn't logged in.
+    const fn require_auth(&self) -> Result<(), ApiError> {
+        if self.authenticated {
+            Ok(())
+        } else {
+            Err(ApiError::NotAuthenticated)
         }
-        let url: serde_json::Value = res
-            .get("url")
-            .ok_or(ApiError::MissingKey("url".to_string()))?
-            .clone();
-        Ok(serde_json::from_value(url)?)
     }
 
-    /// Get the user's favorites of type `T`.
+    /// Set how many times `get_streamable_track_file` queries the file of a track again when
+    /// Qobuz serves a sample, which defaults to `DEFAULT_SAMPLE_RETRIES`.
+    ///
+    /// Qobuz sometimes serves samples of streamable tracks under load. Few retries should be
+    /// used, since tracks may also really be only available as samples.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use qobuz::{auth::Credentials, Client};
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials)
+    ///     .await
+    ///     .unwrap()
+    ///     .with_sample_retries(3);
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn with_sample_retries(mut self, retries: u32) -> Self {
+        self.sample_retries = retries;
+        self
+    }
+
+    /// Cache up to `capacity` track files in memory, so that `get_track_file` and the methods
+    /// using it don't query the same track file again while its URL is still valid. The cache is
+    /// shared between clones of the `Client`.
+    ///
+    /// Use `get_track_file_uncached` when a fresh URL is needed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use qobuz::{auth::Credentials, Client};
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials)
+    ///     .await
+    ///     .unwrap()
+    ///     .with_track_file_cache(1000);
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn with_track_file_cache(mut self, capacity: usize) -> Self {
+        self.track_file_cache = Some(Arc::new(Mutex::new(TrackFileCache::new(capacity))));
+        self
+    }
+
+    /// Cache up to `capacity` responses to item requests, like `get_album` or `get_artist`, in
+    /// memory along with their `ETag`. Requests for cached items are then sent with
+    /// `If-None-Match`, and the cached response is reused when Qobuz answers that it hasn't
+    /// changed, saving bandwidth and latency for applications fetching the same items again. The
+    /// least recently used responses are evicted first, and the cache is shared between clones of
+    /// the `Client`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use qobuz::{auth::Credentials, Client};
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials)
+    ///     .await
+    ///     .unwrap()
+    ///     .with_response_cache(100);
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn with_response_cache(mut self, capacity: usize) -> Self {
+        self.response_cache = Some(Arc::new(Mutex::new(ResponseCache::new(capacity))));
+        self
+    }
+
+    /// Set the timeout of API requests, which defaults to `DEFAULT_REQUEST_TIMEOUT`. It doesn't
+    /// apply to track streams, which can take a long time: see `with_stream_idle_timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use qobuz::{auth::Credentials, Client};
+    /// use std::time::Duration;
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials)
+    ///     .await
+    ///     .unwrap()
+    ///     .with_request_timeout(Duration::from_secs(10));
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Set how long track streams may go without receiving any data before being aborted, which
+    /// defaults to `DEFAULT_STREAM_IDLE_TIMEOUT`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use qobuz::{auth::Credentials, Client};
+    /// use std::time::Duration;
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials)
+    ///     .await
+    ///     .unwrap()
+    ///     .with_stream_idle_timeout(Duration::from_secs(60));
+    /// # })
+    /// ```
+    #[must_use]
+    pub const fn with_stream_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_idle_timeout = timeout;
+        self
+    }
+
+    /// Set the quality used by `get_track_file_url_default`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use qobuz::{auth::Credentials, quality::Quality, Client};
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials)
+    ///     .await
+    ///     .unwrap()
+    ///     .with_default_quality(Quality::Cd);
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn with_default_quality(mut self, quality: Quality) -> Self {
+        self.default_quality = Some(quality);
+        self
+    }
+
+    /// Get information on the logged-in user, as returned at login.
     ///
     /// # Example
     ///
@@ -115,32 +336,23 @@ impl Client {
     /// # use qobuz::{auth::Credentials, Client};
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// use qobuz::types::{Track, extra::WithExtra};
-    /// // Get the user's favorite tracks
-    /// let favorites = client.get_user_favorites::<Track<WithExtra>>().await.unwrap();
+    /// let user = client.get_user_info().unwrap();
+    /// println!("Logged in as {user}, max quality: {:?}", user.max_quality());
     /// # })
     /// ```
-    pub async fn get_user_favorites<T: QobuzType + DeserializeOwned + Favoritable>(
-        &self,
-    ) -> Result<Vec<T>, ApiError> {
-        let fav_type = T::name_plural();
-        let params = [
-            ("type", fav_type),
-            ("limit", "500"),
-            ("offset", "0"), // TODO: walk
-        ];
-        let res: Value = self
-            .do_request("favorite/getUserFavorites", &params)
-            .await?;
-        let array: Value = res
-            .get(fav_type)
-            .ok_or(ApiError::MissingKey(fav_type.to_string()))?
-            .clone();
-        let array: Array<T> = serde_json::from_value(array)?;
-        Ok(array.items)
+    pub fn get_user_info(&self) -> Result<UserInfo, ApiError> {
+        self.require_auth()?;
+        Ok(serde_json::from_value(self.user.clone())?)
     }
 
-    /// Get the user's playlists.
+    /// The best quality the user's subscription allows streaming, if known.
+    #[must_use]
+    pub fn max_allowed_quality(&self) -> Option<Quality> {
+        self.max_quality.clone()
+    }
+
+    /// Lower `requested` to the best quality the user's subscription allows streaming, so that
+    /// files aren't requested in a quality Qobuz would downgrade anyway.
     ///
     /// # Example
     ///
@@ -149,27 +361,44 @@ impl Client {
     /// # use qobuz::{auth::Credentials, Client};
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// // Get the user's playlists
-    /// let playlists = client.get_user_playlists().await.unwrap();
+    /// use qobuz::quality::Quality;
+    /// let quality = client.clamp_quality(Quality::HiRes192);
+    /// println!("Downloading in {quality}");
     /// # })
     /// ```
-    pub async fn get_user_playlists(&self) -> Result<Vec<Playlist<WithoutExtra>>, ApiError> {
-        let params = [
-            ("limit", "500"),
-            ("offset", "0"), // TODO: walk
-        ];
-        let res: Value = self
-            .do_request("playlist/getUserPlaylists", &params)
-            .await?;
-        let array: Value = res
-            .get("playlists")
-            .ok_or(ApiError::MissingKey("playlists".to_string()))?
-            .clone();
-        let array: Array<Playlist<WithoutExtra>> = serde_json::from_value(array)?;
-        Ok(array.items)
+    #[must_use]
+    pub fn clamp_quality(&self, requested: Quality) -> Quality {
+        match &self.max_quality {
+            Some(max) => requested.min(max.clone()),
+            None => requested,
+        }
     }
 
-    /// Get information on an item.
+    /// Log out, invalidating the user auth token used by this `Client`.
+    ///
+    /// The client is consumed, as it can't be used to make requests anymore.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use qobuz::{auth::Credentials, Client};
+    /// let credentials = Credentials::from_env().unwrap();
+    /// let client = Client::new(credentials).await.unwrap();
+    /// client.logout().await.unwrap();
+    /// # })
+    /// ```
+    pub async fn logout(self) -> Result<(), ApiError> {
+        self.require_auth()?;
+        let _: Value = self.do_request("user/logout", &[]).await?;
+        Ok(())
+    }
+
+    /// Get the download URL of a track. The track ID can be given as a string or a number.
+    ///
+    /// If Qobuz only serves the track in a lower quality than the requested one, e.g. because the
+    /// account can't stream Hi-Res tracks, `ApiError::QualityUnavailable` is returned. Use
+    /// `get_track_file` to accept the delivered quality instead.
     ///
     /// # Example
     ///
@@ -178,32 +407,38 @@ impl Client {
     /// # use qobuz::{auth::Credentials, Client};
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// use qobuz::{types::Track, types::extra::WithExtra};
-    /// // Get information on "Let It Be" (the track)
+    /// use qobuz::quality::Quality;
+    /// // Get download URL of "Let it Be" (the track)
     /// let track = client
-    ///     .get_item::<Track<WithExtra>>("129342731")
+    ///     .get_track_file_url("129342731", Quality::HiRes96)
     ///     .await
     ///     .unwrap();
     /// # })
     /// ```
-    pub async fn get_item<T>(&self, id: &str) -> Result<T, ApiError>
-    where
-        T: QobuzType + RootEntity + DeserializeOwned,
-    {
-        Ok(self
-            .do_request(
-                &format!("{}/get", T::name_singular()),
-                &[
-                    (format!("{}_id", T::name_singular()).as_str(), id),
-                    ("extra", T::extra_arg()),
-                    ("limit", "500"), // TODO: walk
-                    ("offset", "0"),
-                ],
-            )
-            .await?)
+    pub async fn get_track_file_url(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<url::Url, ApiError> {
+        let file = self.get_track_file(track_id, quality.clone()).await?;
+        if file.sample {
+            return Err(ApiError::IsSample);
+        }
+        if file.quality != quality {
+            return Err(ApiError::QualityUnavailable {
+                requested: quality,
+                delivered: file.quality,
+            });
+        }
+        Ok(file.url)
     }
 
-    /// Get information on a track.
+    /// Get the download URL of a track like `get_track_file_url`, along with the time it stops
+    /// being valid, e.g. to hand it to an external downloader and know when to get it again.
+    ///
+    /// The expiry is read from the `etsp` parameter of the signed URL, a UNIX timestamp. It is
+    /// `None` if the URL has no such parameter, in which case its lifetime is unknown and it
+    /// should be used right away.
     ///
     /// # Example
     ///
@@ -212,18 +447,28 @@ impl Client {
     /// # use qobuz::{auth::Credentials, Client};
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// // Get information on "Let It Be" (the track)
-    /// let track = client
-    ///     .get_track("129342731")
+    /// use qobuz::quality::Quality;
+    /// let (url, expiry) = client
+    ///     .get_track_file_url_with_expiry("129342731", Quality::Cd)
     ///     .await
     ///     .unwrap();
+    /// if let Some(expiry) = expiry {
+    ///     println!("{url} is valid until {expiry}");
+    /// }
     /// # })
     /// ```
-    pub async fn get_track(&self, track_id: &str) -> Result<Track<WithExtra>, ApiError> {
-        self.get_item(track_id).await
+    pub async fn get_track_file_url_with_expiry(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<(url::Url, Option<DateTime<Utc>>), ApiError> {
+        let url = self.get_track_file_url(track_id, quality).await?;
+        let expiry = cache::url_expiry(&url).map(DateTime::<Utc>::from);
+        Ok((url, expiry))
     }
 
-    /// Get information on a playlist.
+    /// Get the file of a track served by Qobuz for the requested quality, along with the quality
+    /// it is actually delivered in and the restrictions that apply to it.
     ///
     /// # Example
     ///
@@ -232,18 +477,40 @@ impl Client {
     /// # use qobuz::{auth::Credentials, Client};
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// // Get information on an official Beatles playlist
-    /// let playlist = client
-    ///     .get_playlist("1141084")
+    /// use qobuz::quality::Quality;
+    /// // Get the best available file of "Let it Be" (the track), up to Hi-Res 192 kHz
+    /// let file = client
+    ///     .get_track_file("129342731", Quality::HiRes192)
     ///     .await
     ///     .unwrap();
+    /// println!("Delivered in {}", file.quality);
     /// # })
     /// ```
-    pub async fn get_playlist(&self, playlist_id: &str) -> Result<Playlist<WithExtra>, ApiError> {
-        self.get_item(playlist_id).await
+    pub async fn get_track_file(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<TrackFile, ApiError> {
+        let track_id = track_id.to_string();
+        let quality_id: u8 = quality.clone().into();
+        let Some(cache) = &self.track_file_cache else {
+            return self.get_track_file_uncached(track_id, quality).await;
+        };
+        if let Some(file) = lock_cache(cache).get(&track_id, quality_id) {
+            return Ok(file);
+        }
+        let file = self.get_track_file_uncached(&track_id, quality).await?;
+        // Samples may be served by mistake, so don't keep them.
+        if !file.sample {
+            lock_cache(cache).insert(&track_id, quality_id, file.clone());
+        }
+        Ok(file)
     }
 
-    /// Get information on an album.
+    /// Get the file of a track known to be streamable like `get_track_file`, querying it again
+    /// up to the number of times set with `with_sample_retries` if a sample is served.
+    ///
+    /// A sample may still be returned if it is served for every retry.
     ///
     /// # Example
     ///
@@ -252,18 +519,38 @@ impl Client {
     /// # use qobuz::{auth::Credentials, Client};
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// // Get information on "Abbey Road"
-    /// let album = client
-    ///     .get_album("trrcz9pvaaz6b")
+    /// use qobuz::quality::Quality;
+    /// // Get the file of "Let it Be" (the track), which is streamable
+    /// let track = client.get_track("129342731").await.unwrap();
+    /// assert!(track.streamable);
+    /// let file = client
+    ///     .get_streamable_track_file(track.id, Quality::Cd)
     ///     .await
     ///     .unwrap();
     /// # })
     /// ```
-    pub async fn get_album(&self, album_id: &str) -> Result<Album<WithExtra>, ApiError> {
-        self.get_item(album_id).await
+    pub async fn get_streamable_track_file(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<TrackFile, ApiError> {
+        let track_id = track_id.to_string();
+        let mut file = self.get_track_file(&track_id, quality.clone()).await?;
+        for _ in 0..self.sample_retries {
+            if !file.sample {
+                break;
+            }
+            // Requests are signed with a timestamp in seconds, so wait for it to change.
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            file = self
+                .get_track_file_uncached(&track_id, quality.clone())
+                .await?;
+        }
+        Ok(file)
     }
 
-    /// Get information on an artist.
+    /// Get the file of a track like `get_track_file`, bypassing the cache enabled with
+    /// `with_track_file_cache`. The returned file isn't cached either.
     ///
     /// # Example
     ///
@@ -272,18 +559,1024 @@ impl Client {
     /// # use qobuz::{auth::Credentials, Client};
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// // Get information on the Beatles
-    /// let artist = client
-    ///     .get_artist("26390")
+    /// use qobuz::quality::Quality;
+    /// // Get a fresh URL for "Let it Be" (the track)
+    /// let file = client
+    ///     .get_track_file_uncached("129342731", Quality::Cd)
     ///     .await
     ///     .unwrap();
     /// # })
     /// ```
-    pub async fn get_artist(&self, artist_id: &str) -> Result<Artist<WithExtra>, ApiError> {
-        self.get_item(artist_id).await
+    pub async fn get_track_file_uncached(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<TrackFile, ApiError> {
+        let track_id = track_id.to_string();
+        let res = self
+            .get_track_file_url_response(&track_id, quality.clone())
+            .await?;
+        let file: TrackFile = serde_json::from_value(res)?;
+        tracing::debug!(
+            track_id,
+            requested = %quality,
+            delivered = %file.quality,
+            sample = file.sample,
+            "got track file"
+        );
+        Ok(file)
+    }
+
+    /// Get the download URL of a track in the default quality set with `with_default_quality`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// use qobuz::quality::Quality;
+    /// let client = Client::new(credentials)
+    ///     .await
+    ///     .unwrap()
+    ///     .with_default_quality(Quality::HiRes96);
+    /// // Get download URL of "Let it Be" (the track)
+    /// let track = client
+    ///     .get_track_file_url_default("129342731")
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_track_file_url_default(
+        &self,
+        track_id: impl Display,
+    ) -> Result<url::Url, ApiError> {
+        let quality = self
+            .default_quality
+            .clone()
+            .ok_or(ApiError::NoDefaultQuality)?;
+        self.get_track_file_url(track_id, quality).await
     }
 
-    /// Stream a track.
+    /// Check whether a track can be streamed in the given quality.
+    ///
+    /// The file of the track is queried with `get_streamable_track_file`, so the track is only
+    /// reported as restricted to a sample if Qobuz serves one for every retry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::{quality::Quality, types::Availability};
+    /// // Check whether "Let it Be" (the track) is available in Hi-Res
+    /// let availability = client
+    ///     .check_track_availability("129342731", Quality::HiRes96)
+    ///     .await
+    ///     .unwrap();
+    /// if let Availability::Unavailable(reason) = availability {
+    ///     println!("Can't stream track: {reason}");
+    /// }
+    /// # })
+    /// ```
+    pub async fn check_track_availability(
+        &self,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<Availability, ApiError> {
+        let track_id = track_id.to_string();
+        let track = self.get_track(&track_id).await?;
+        if let Some(reason) = track.unavailability_reason(Utc::now()) {
+            return Ok(Availability::Unavailable(reason));
+        }
+        let file = self
+            .get_streamable_track_file(&track_id, quality.clone())
+            .await?;
+        Ok(file.availability(&quality))
+    }
+
+    async fn get_track_file_url_response(
+        &self,
+        track_id: &str,
+        quality: Quality,
+    ) -> Result<Value, ApiError> {
+        self.require_auth()?;
+        let quality_id: u8 = quality.into();
+        let quality_id = quality_id.to_string();
+        let params = [
+            ("track_id", track_id),
+            ("format_id", quality_id.as_str()),
+            ("intent", "stream"),
+        ];
+        let (request_ts, request_sig) = self.sign_request("track/getFileUrl", &params);
+        let params = [
+            ("request_ts", request_ts.as_str()),
+            ("request_sig", request_sig.as_str()),
+            ("track_id", track_id),
+            ("format_id", quality_id.as_str()),
+            ("intent", "stream"),
+        ];
+        Ok(self.do_request("track/getFileUrl", &params).await?)
+    }
+
+    /// Sign a request to an endpoint that requires it, returning the `request_ts` and
+    /// `request_sig` parameters to add to the request.
+    ///
+    /// `object_method` is the path of the endpoint, e.g. `track/getFileUrl`, and `params` the
+    /// other parameters of the request.
+    fn sign_request(&self, object_method: &str, params: &[(&str, &str)]) -> (String, String) {
+        let timestamp_now = chrono::Utc::now().timestamp().to_string();
+        let request_sig = compute_request_sig(object_method, params, &timestamp_now, &self.secret);
+        (timestamp_now, request_sig)
+    }
+
+    /// Get the user's favorites of type `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::types::{Track, extra::WithExtra};
+    /// // Get the user's favorite tracks
+    /// let favorites = client.get_user_favorites::<Track<WithExtra>>().await.unwrap();
+    /// # })
+    /// ```
+    pub async fn get_user_favorites<T: QobuzType + DeserializeOwned + Favoritable>(
+        &self,
+    ) -> Result<Vec<T>, ApiError> {
+        self.get_user_favorites_stream().try_collect().await
+    }
+
+    /// Get the user's favorites of type `T` as a stream, querying them page by page as the
+    /// stream is consumed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use futures::TryStreamExt;
+    /// use qobuz::types::{Track, extra::WithExtra};
+    /// // Print the user's favorite tracks
+    /// let mut favorites = std::pin::pin!(client.get_user_favorites_stream::<Track<WithExtra>>());
+    /// while let Some(track) = favorites.try_next().await.unwrap() {
+    ///     println!("{track}");
+    /// }
+    /// # })
+    /// ```
+    pub fn get_user_favorites_stream<T: QobuzType + DeserializeOwned + Favoritable>(
+        &self,
+    ) -> impl Stream<Item = Result<T, ApiError>> + '_ {
+        stream::try_unfold(Some(0), move |offset| async move {
+            let Some(offset) = offset else {
+                return Ok::<_, ApiError>(None);
+            };
+            let page: Array<T> = self.get_user_favorites_page(offset).await?;
+            let next_offset = page.next_offset();
+            Ok(Some((
+                stream::iter(page.items.into_iter().map(Ok)),
+                next_offset,
+            )))
+        })
+        .try_flatten()
+    }
+
+    async fn get_user_favorites_page<T: QobuzType + DeserializeOwned + Favoritable>(
+        &self,
+        offset: i64,
+    ) -> Result<Array<T>, ApiError> {
+        self.require_auth()?;
+        let fav_type = T::name_plural();
+        let offset = offset.to_string();
+        let limit = PAGE_SIZE.to_string();
+        let params = [
+            ("type", fav_type),
+            ("limit", limit.as_str()),
+            ("offset", offset.as_str()),
+        ];
+        let res: Value = self
+            .do_request("favorite/getUserFavorites", &params)
+            .await?;
+        let array: Value = res
+            .get(fav_type)
+            .ok_or(ApiError::MissingKey(fav_type.to_string()))?
+            .clone();
+        Ok(serde_json::from_value(array)?)
+    }
+
+    /// Query a single page of a paginated endpoint, for callers who want to handle pagination
+    /// themselves, e.g. to resume from a saved offset.
+    ///
+    /// The page is read from the `key` field of the response, and `Array::next_offset` gives the
+    /// offset of the following page.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::types::{extra::WithoutExtra, Album, Array};
+    /// // Print the user's favorite albums, 50 at a time
+    /// let mut offset = Some(0);
+    /// while let Some(o) = offset {
+    ///     let page: Array<Album<WithoutExtra>> = client
+    ///         .get_page(
+    ///             "favorite/getUserFavorites",
+    ///             &[("type", "albums")],
+    ///             "albums",
+    ///             o,
+    ///             50,
+    ///         )
+    ///         .await
+    ///         .unwrap();
+    ///     for album in &page.items {
+    ///         println!("{album}");
+    ///     }
+    ///     offset = page.next_offset();
+    /// }
+    /// # })
+    /// ```
+    pub async fn get_page<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+        key: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Array<T>, ApiError> {
+        let offset = offset.to_string();
+        let limit = limit.to_string();
+        let mut params = params.to_vec();
+        params.extend([("offset", offset.as_str()), ("limit", limit.as_str())]);
+        let res: Value = self.do_request(path, &params).await?;
+        let array: Value = res
+            .get(key)
+            .ok_or(ApiError::MissingKey(key.to_string()))?
+            .clone();
+        Ok(serde_json::from_value(array)?)
+    }
+
+    /// Check whether the item of type `T` with the given ID is one of the user's favorites.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::types::{Track, extra::WithExtra};
+    /// // Check whether "Let It Be" (the track) is a favorite
+    /// let is_favorite = client
+    ///     .is_favorite::<Track<WithExtra>>("129342731")
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn is_favorite<T: QobuzType + Favoritable>(
+        &self,
+        id: &str,
+    ) -> Result<bool, ApiError> {
+        self.require_auth()?;
+        let params = [("item_id", id), ("type", T::name_singular())];
+        let res: Value = self.do_request("favorite/status", &params).await?;
+        res.get("status")
+            .and_then(Value::as_bool)
+            .ok_or(ApiError::MissingKey("status".to_string()))
+    }
+
+    /// Get the user's playlists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get the user's playlists
+    /// let playlists = client.get_user_playlists().await.unwrap();
+    /// # })
+    /// ```
+    pub async fn get_user_playlists(&self) -> Result<Vec<Playlist<WithoutExtra>>, ApiError> {
+        self.require_auth()?;
+        let mut playlists = Vec::new();
+        let mut offset = Some(0);
+        while let Some(o) = offset {
+            let page: Array<Playlist<WithoutExtra>> = self
+                .get_page("playlist/getUserPlaylists", &[], "playlists", o, PAGE_SIZE)
+                .await?;
+            offset = page.next_offset();
+            playlists.extend(page.items);
+        }
+        Ok(playlists)
+    }
+
+    /// Get playlists curated by Qobuz editors, e.g. to show them in a browse page, optionally
+    /// only the ones with the given tag, like `"focus"` or `"hi-res"`. Up to `limit` playlists
+    /// are returned, most recent first.
+    ///
+    /// This doesn't require logging in, see `Client::public`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get the latest 10 editorial playlists
+    /// let playlists = client.get_featured_playlists(None, 10).await.unwrap();
+    /// for playlist in playlists {
+    ///     println!("{}", playlist.name);
+    /// }
+    /// # })
+    /// ```
+    pub async fn get_featured_playlists(
+        &self,
+        tag: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<Playlist<WithoutExtra>>, ApiError> {
+        let limit = limit.to_string();
+        let mut params = vec![
+            ("type", "editor-picks"),
+            ("limit", limit.as_str()),
+            ("offset", "0"),
+        ];
+        if let Some(tag) = tag {
+            params.push(("tags", tag));
+        }
+        let res: Value = self.do_request("playlist/getFeatured", &params).await?;
+        let array: Value = res
+            .get("playlists")
+            .ok_or(ApiError::MissingKey("playlists".to_string()))?
+            .clone();
+        let array: Array<Playlist<WithoutExtra>> = serde_json::from_value(array)?;
+        Ok(array.items)
+    }
+
+    /// Get information on an item.
+    ///
+    /// Only the first 500 items of its extra, e.g. the albums of an artist, are included. Use
+    /// `get_item_page` to get the following ones, or `get_album` and `get_playlist`, which get
+    /// all the tracks of albums and playlists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::{types::Track, types::extra::WithExtra};
+    /// // Get information on "Let It Be" (the track)
+    /// let track = client
+    ///     .get_item::<Track<WithExtra>>("129342731")
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_item<T>(&self, id: &str) -> Result<T, ApiError>
+    where
+        T: QobuzType + RootEntity + DeserializeOwned,
+    {
+        self.get_item_page(id, 0, PAGE_SIZE).await
+    }
+
+    /// Get information on an item like `get_item`, with only the page of its extra (e.g. the
+    /// tracks of an album or the albums of an artist) starting at `offset` and holding up to
+    /// `limit` items.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::{types::Album, types::extra::WithExtra};
+    /// // Get information on "Abbey Road" with its first 5 tracks
+    /// let album = client
+    ///     .get_item_page::<Album<WithExtra>>("trrcz9pvaaz6b", 0, 5)
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_item_page<T>(&self, id: &str, offset: i64, limit: i64) -> Result<T, ApiError>
+    where
+        T: QobuzType + RootEntity + DeserializeOwned,
+    {
+        let offset = offset.to_string();
+        let limit = limit.to_string();
+        self.do_cached_request(
+            &format!("{}/get", T::name_singular()),
+            &[
+                (format!("{}_id", T::name_singular()).as_str(), id),
+                ("extra", T::extra_arg()),
+                ("limit", limit.as_str()),
+                ("offset", offset.as_str()),
+            ],
+        )
+        .await
+    }
+
+    /// Get information on a track.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get information on "Let It Be" (the track)
+    /// let track = client
+    ///     .get_track("129342731")
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_track(&self, track_id: &str) -> Result<Track<WithExtra>, ApiError> {
+        self.get_item(track_id).await
+    }
+
+    /// Get information on a track, choosing with `EF` whether to keep its album: `WithExtra` is
+    /// the same as `get_track`, while `WithoutExtra` leaves it out, for callers that don't need
+    /// it and don't want to hold on to it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::types::extra::WithoutExtra;
+    /// // Get information on "Let It Be" (the track), without its album
+    /// let track = client
+    ///     .get_track_with::<WithoutExtra>("129342731")
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_track_with<EF>(&self, track_id: &str) -> Result<Track<EF>, ApiError>
+    where
+        EF: ExtraFlag<Album<WithoutExtra>>,
+        Track<EF>: RootEntity + DeserializeOwned,
+    {
+        self.get_item(track_id).await
+    }
+
+    /// Get information on a track along with its full album, e.g. to tag a single track with
+    /// complete album data like its number of tracks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get information on "Let It Be" (the track) and its album
+    /// let (track, album) = client
+    ///     .get_track_with_full_album("129342731")
+    ///     .await
+    ///     .unwrap();
+    /// println!("{track} has {} siblings", album.tracks.items.len() - 1);
+    /// # })
+    /// ```
+    pub async fn get_track_with_full_album(
+        &self,
+        track_id: &str,
+    ) -> Result<(Track<WithExtra>, Album<WithExtra>), ApiError> {
+        let track = self.get_track(track_id).await?;
+        let album = self.get_full_album_of(&track).await?;
+        Ok((track, album))
+    }
+
+    /// Get the full album of an already fetched track, see `get_track_with_full_album`.
+    pub(crate) async fn get_full_album_of(
+        &self,
+        track: &Track<WithExtra>,
+    ) -> Result<Album<WithExtra>, ApiError> {
+        self.get_album(&track.album.id).await
+    }
+
+    /// Get the credits of a track, e.g. its composers, producers and engineers. See
+    /// `Track::credits` to get them from an already fetched track.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get the credits of "Let It Be" (the track)
+    /// let credits = client.get_track_credits("129342731").await.unwrap();
+    /// for credit in credits {
+    ///     println!("{}: {}", credit.role, credit.name);
+    /// }
+    /// # })
+    /// ```
+    pub async fn get_track_credits(&self, track_id: &str) -> Result<Vec<Credit>, ApiError> {
+        Ok(self.get_track(track_id).await?.credits())
+    }
+
+    /// Get information on multiple tracks at once.
+    ///
+    /// The tracks are fetched concurrently. The ones found are returned in the same order as
+    /// `track_ids`, along with the IDs of the tracks that don't exist. Any other error, e.g. an
+    /// authentication error, is returned as is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get information on "Let It Be" and "Lodi"
+    /// let (tracks, missing) = client
+    ///     .get_tracks(&["129342731", "18893849"])
+    ///     .await
+    ///     .unwrap();
+    /// assert!(missing.is_empty());
+    /// # })
+    /// ```
+    pub async fn get_tracks(
+        &self,
+        track_ids: &[&str],
+    ) -> Result<(Vec<Track<WithExtra>>, Vec<String>), ApiError> {
+        let results: Vec<_> = stream::iter(track_ids)
+            .map(|id| async move { (*id, self.get_track(id).await) })
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .collect()
+            .await;
+        let mut tracks = Vec::with_capacity(results.len());
+        let mut missing = Vec::new();
+        for (id, res) in results {
+            match res {
+                Ok(track) => tracks.push(track),
+                Err(ApiError::ReqwestError(e))
+                    if e.status() == Some(reqwest::StatusCode::NOT_FOUND) =>
+                {
+                    missing.push(id.to_string());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok((tracks, missing))
+    }
+
+    /// Compute the total size in bytes of the files of `tracks` in the given quality, e.g. to
+    /// show how much an album or playlist download will take before starting it.
+    ///
+    /// With `SizeEstimate::Exact`, the size of each file is requested from Qobuz, up to
+    /// `MAX_CONCURRENT_REQUESTS` at a time, falling back to an estimate for files whose size
+    /// isn't given. With `SizeEstimate::FromDuration`, sizes are estimated with
+    /// `Track::estimated_size` without any request.
+    ///
+    /// Tracks that can't be downloaded, e.g. because they aren't streamable or only a sample of
+    /// them is, are skipped like the downloader would, instead of failing the whole estimate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::{quality::Quality, types::SizeEstimate};
+    /// // Get the size of "Abbey Road" in CD quality
+    /// let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+    /// let size = client
+    ///     .estimate_download_size(&album.tracks.items, Quality::Cd, SizeEstimate::Exact)
+    ///     .await
+    ///     .unwrap();
+    /// println!("{:.1} MB", size as f64 / 1e6);
+    /// # })
+    /// ```
+    pub async fn estimate_download_size<EF>(
+        &self,
+        tracks: &[Track<EF>],
+        quality: Quality,
+        mode: SizeEstimate,
+    ) -> Result<u64, ApiError>
+    where
+        EF: ExtraFlag<Album<WithoutExtra>>,
+        EF::Extra: Sync,
+    {
+        if mode == SizeEstimate::FromDuration {
+            let now = Utc::now();
+            return Ok(tracks
+                .iter()
+                .filter(|track| track.unavailability_reason(now).is_none())
+                .map(|track| track.estimated_size(&quality))
+                .sum());
+        }
+        let sizes: Vec<_> = stream::iter(tracks)
+            .map(|track| {
+                let quality = quality.clone();
+                async move {
+                    if let Some(reason) = track.unavailability_reason(Utc::now()) {
+                        tracing::debug!("Skipping track {} in size estimate: {reason}", track.id);
+                        return Ok(0);
+                    }
+                    let file = self.get_track_file(track.id, quality).await?;
+                    if file.sample {
+                        tracing::debug!(
+                            "Skipping track {} in size estimate: only a sample is available",
+                            track.id
+                        );
+                        return Ok(0);
+                    }
+                    let size = self
+                        .reqwest_client
+                        .head(file.url)
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .content_length();
+                    Ok::<_, ApiError>(size.unwrap_or_else(|| track.estimated_size(&file.quality)))
+                }
+            })
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .try_collect()
+            .await?;
+        Ok(sizes.into_iter().sum())
+    }
+
+    /// Search the catalog for tracks with the given ISRC, returning all matches.
+    ///
+    /// Several tracks can share an ISRC, e.g. when a recording appears on a compilation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Find all releases of "Let It Be" (the track)
+    /// let tracks = client.search_tracks_by_isrc("GBAYE0601713").await.unwrap();
+    /// # })
+    /// ```
+    pub async fn search_tracks_by_isrc(
+        &self,
+        isrc: &str,
+    ) -> Result<Vec<Track<WithExtra>>, ApiError> {
+        let normalize = |isrc: &str| isrc.replace('-', "").to_uppercase();
+        let isrc = normalize(isrc);
+        let page: Array<Track<WithExtra>> = self
+            .get_page("track/search", &[("query", &isrc)], "tracks", 0, 50)
+            .await?;
+        Ok(page
+            .items
+            .into_iter()
+            .filter(|track| normalize(&track.isrc) == isrc)
+            .collect())
+    }
+
+    /// Get a track with the given ISRC, or `None` if there is none in the catalog.
+    ///
+    /// Matches aren't guaranteed to be the wanted release when several tracks share the ISRC: use
+    /// `search_tracks_by_isrc` to get all of them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Find "Let It Be" (the track)
+    /// let track = client.get_track_by_isrc("GBAYE0601713").await.unwrap();
+    /// # })
+    /// ```
+    pub async fn get_track_by_isrc(
+        &self,
+        isrc: &str,
+    ) -> Result<Option<Track<WithExtra>>, ApiError> {
+        Ok(self.search_tracks_by_isrc(isrc).await?.into_iter().next())
+    }
+
+    /// Search the catalog for albums with the given UPC, returning all matches.
+    ///
+    /// UPCs are compared regardless of leading zeros, so that 12-digit UPCs match their 13-digit
+    /// EAN form used by Qobuz.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Find "Abbey Road"
+    /// let albums = client.search_albums_by_upc("0602577915052").await.unwrap();
+    /// # })
+    /// ```
+    pub async fn search_albums_by_upc(
+        &self,
+        upc: &str,
+    ) -> Result<Vec<Album<WithoutExtra>>, ApiError> {
+        let normalize = |upc: &str| upc.trim().trim_start_matches('0').to_string();
+        let upc = normalize(upc);
+        let page: Array<Album<WithoutExtra>> = self
+            .get_page("album/search", &[("query", &upc)], "albums", 0, 50)
+            .await?;
+        Ok(page
+            .items
+            .into_iter()
+            .filter(|album| normalize(&album.upc) == upc)
+            .collect())
+    }
+
+    /// Get an album with the given UPC, or `None` if there is none in the catalog.
+    ///
+    /// Use `search_albums_by_upc` to get all matches, and `get_album` to get the album's tracks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Find "Abbey Road"
+    /// let album = client.get_album_by_upc("0602577915052").await.unwrap();
+    /// # })
+    /// ```
+    pub async fn get_album_by_upc(
+        &self,
+        upc: &str,
+    ) -> Result<Option<Album<WithoutExtra>>, ApiError> {
+        Ok(self.search_albums_by_upc(upc).await?.into_iter().next())
+    }
+
+    /// Get information on a playlist with all of its tracks, walking through all their pages.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get information on an official Beatles playlist
+    /// let playlist = client
+    ///     .get_playlist("1141084")
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_playlist(&self, playlist_id: &str) -> Result<Playlist<WithExtra>, ApiError> {
+        let mut playlist: Playlist<WithExtra> = self.get_item(playlist_id).await?;
+        while let Some(offset) = playlist.tracks.next_offset() {
+            let page: Playlist<WithExtra> =
+                self.get_item_page(playlist_id, offset, PAGE_SIZE).await?;
+            if !playlist.tracks.append(page.tracks) {
+                break;
+            }
+        }
+        Ok(playlist)
+    }
+
+    /// Download the cover of an album in the given size.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::types::CoverSize;
+    /// // Save the cover of "Abbey Road" in the highest resolution
+    /// let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+    /// let cover = client.download_cover(&album, CoverSize::Max).await.unwrap();
+    /// std::fs::write("cover.jpg", cover).unwrap();
+    /// # })
+    /// ```
+    pub async fn download_cover<EF>(
+        &self,
+        album: &Album<EF>,
+        size: CoverSize,
+    ) -> Result<Bytes, ApiError>
+    where
+        EF: ExtraFlag<Array<Track<WithoutExtra>>>,
+    {
+        let url = album
+            .image
+            .url(size)
+            .ok_or_else(|| ApiError::InvalidCoverUrl(album.image.large.clone()))?;
+        Ok(self
+            .reqwest_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?)
+    }
+
+    /// Get a page of the tracks of a playlist, without the rest of the playlist's information.
+    ///
+    /// This queries the same endpoint as `get_playlist`, so it isn't any lighter, but it allows
+    /// reading playlists with more tracks than `get_playlist` returns. Use `Array::next_offset`
+    /// to get the following page.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get the IDs of the first 50 tracks of an official Beatles playlist
+    /// let tracks = client
+    ///     .get_playlist_tracks("1141084", 0, 50)
+    ///     .await
+    ///     .unwrap();
+    /// let ids: Vec<u64> = tracks.items.iter().map(|t| t.id).collect();
+    /// # })
+    /// ```
+    pub async fn get_playlist_tracks(
+        &self,
+        playlist_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Array<Track<WithExtra>>, ApiError> {
+        self.get_page(
+            "playlist/get",
+            &[("playlist_id", playlist_id), ("extra", "tracks")],
+            "tracks",
+            offset,
+            limit,
+        )
+        .await
+    }
+
+    /// Get information on an album with all of its tracks, walking through all their pages.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get information on "Abbey Road"
+    /// let album = client
+    ///     .get_album("trrcz9pvaaz6b")
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_album(&self, album_id: &str) -> Result<Album<WithExtra>, ApiError> {
+        let mut album: Album<WithExtra> = self.get_item(album_id).await?;
+        while let Some(offset) = album.tracks.next_offset() {
+            let page: Album<WithExtra> = self.get_item_page(album_id, offset, PAGE_SIZE).await?;
+            if !album.tracks.append(page.tracks) {
+                break;
+            }
+        }
+        Ok(album)
+    }
+
+    /// Get information on an artist with their first 500 tracks and albums. Use
+    /// `get_all_artist_tracks` and `get_artist_albums` to get all of them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get information on the Beatles
+    /// let artist = client
+    ///     .get_artist("26390")
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_artist(&self, artist_id: &str) -> Result<Artist<WithExtra>, ApiError> {
+        self.get_item(artist_id).await
+    }
+
+    /// Get a page of the albums of an artist of the given release type, e.g. to show their
+    /// discography grouped by release type.
+    ///
+    /// Albums are also filtered by their `release_type`, so a page may contain fewer than
+    /// `limit` albums. Albums whose release type isn't known are kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// use qobuz::types::ReleaseType;
+    /// // Get the first 20 live albums of The Beatles
+    /// let albums = client
+    ///     .get_artist_albums("26390", ReleaseType::Live, 0, 20)
+    ///     .await
+    ///     .unwrap();
+    /// for album in &albums.items {
+    ///     println!("{album}");
+    /// }
+    /// # })
+    /// ```
+    pub async fn get_artist_albums(
+        &self,
+        artist_id: &str,
+        release_type: ReleaseType,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Array<Album<WithoutExtra>>, ApiError> {
+        let mut page: Array<Album<WithoutExtra>> = self
+            .get_page(
+                "artist/get",
+                &[
+                    ("artist_id", artist_id),
+                    ("extra", "albums"),
+                    ("release_type", release_type.api_name()),
+                ],
+                "albums",
+                offset,
+                limit,
+            )
+            .await?;
+        page.items.retain(|album| {
+            album
+                .release_type
+                .as_deref()
+                .and_then(ReleaseType::from_api_name)
+                .is_none_or(|album_type| album_type == release_type)
+        });
+        Ok(page)
+    }
+
+    /// Get all the tracks of an artist, walking through all the pages of their tracks.
+    ///
+    /// Tracks appearing on several albums, e.g. on compilations, are only returned once, as
+    /// identified by their ISRC.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Get all tracks of The Beatles
+    /// let tracks = client.get_all_artist_tracks("26390").await.unwrap();
+    /// # })
+    /// ```
+    pub async fn get_all_artist_tracks(
+        &self,
+        artist_id: &str,
+    ) -> Result<Vec<Track<WithExtra>>, ApiError> {
+        let mut tracks = Vec::new();
+        let mut isrcs = HashSet::new();
+        let mut offset = Some(0);
+        while let Some(o) = offset {
+            let page: Array<Track<WithExtra>> = self
+                .get_page(
+                    "artist/get",
+                    &[("artist_id", artist_id), ("extra", "tracks")],
+                    "tracks",
+                    o,
+                    500,
+                )
+                .await?;
+            offset = page.next_offset();
+            tracks.extend(
+                page.items
+                    .into_iter()
+                    // Tracks without an ISRC can't be deduplicated.
+                    .filter(|track| track.isrc.is_empty() || isrcs.insert(track.isrc.clone())),
+            );
+        }
+        Ok(tracks)
+    }
+
+    /// Stream a track in the requested quality.
+    ///
+    /// Like `get_track_file_url`, `ApiError::QualityUnavailable` is returned if Qobuz only serves
+    /// the track in a lower quality, and `ApiError::IsSample` if it only serves a sample.
     ///
     /// # Example
     ///
@@ -294,9 +1587,9 @@ impl Client {
     /// # use qobuz::{auth::Credentials, Client, quality::Quality};
     /// # let credentials = Credentials::from_env().unwrap();
     /// # let client = Client::new(credentials).await.unwrap();
-    /// // Download the "Let It Be" track to test.mp3
+    /// // Download the "Let It Be" track to let_it_be.mp3
     /// let mut bytes_stream = client
-    ///     .stream_track("129342731", Quality::HiRes96)
+    ///     .stream_track("129342731", Quality::Mp3)
     ///     .await
     ///     .unwrap();
     /// let mut out = File::create("let_it_be.mp3")
@@ -311,11 +1604,108 @@ impl Client {
     /// ```
     pub async fn stream_track(
         &self,
-        track_id: &str,
+        track_id: impl Display,
+        quality: Quality,
+    ) -> Result<impl Stream<Item = Result<Bytes, ApiError>> + Unpin, ApiError> {
+        let url = self.get_track_file_url(track_id, quality).await?;
+        let res = self
+            .reqwest_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(with_idle_timeout(
+            res.bytes_stream(),
+            self.stream_idle_timeout,
+        ))
+    }
+
+    /// Stream a range of bytes of a track like `stream_track`, e.g. to seek within it, returning
+    /// the stream along with the total size of the track file in bytes.
+    ///
+    /// The range starts at byte `start` and ends at byte `end` included, or at the end of the
+    /// file if `end` is `None`. If the server ignores the range and sends the whole file,
+    /// `ApiError::RangeNotSupported` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use futures::StreamExt;
+    /// # use qobuz::{auth::Credentials, Client, quality::Quality};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Stream the second half of the "Let It Be" track
+    /// let (_, total) = client
+    ///     .stream_track_range("129342731", Quality::Cd, 0, Some(0))
+    ///     .await
+    ///     .unwrap();
+    /// let (mut bytes_stream, _) = client
+    ///     .stream_track_range("129342731", Quality::Cd, total / 2, None)
+    ///     .await
+    ///     .unwrap();
+    /// while let Some(item) = bytes_stream.next().await {
+    ///     println!("Got {} bytes", item.unwrap().len());
+    /// }
+    /// # })
+    /// ```
+    pub async fn stream_track_range(
+        &self,
+        track_id: impl Display,
         quality: Quality,
-    ) -> Result<impl Stream<Item = reqwest::Result<Bytes>>, ApiError> {
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<(impl Stream<Item = Result<Bytes, ApiError>> + Unpin, u64), ApiError> {
         let url = self.get_track_file_url(track_id, quality).await?;
-        Ok(self.reqwest_client.get(url).send().await?.bytes_stream())
+        let range = match end {
+            Some(end) => format!("bytes={start}-{end}"),
+            None => format!("bytes={start}-"),
+        };
+        let res = self
+            .reqwest_client
+            .get(url)
+            .header(reqwest::header::RANGE, range)
+            .send()
+            .await?
+            .error_for_status()?;
+        if res.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(ApiError::RangeNotSupported);
+        }
+        // The Content-Range header has the form `bytes <start>-<end>/<total>`.
+        let total = res
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit_once('/'))
+            .and_then(|(_, total)| total.parse().ok())
+            .ok_or(ApiError::RangeNotSupported)?;
+        Ok((
+            with_idle_timeout(res.bytes_stream(), self.stream_idle_timeout),
+            total,
+        ))
+    }
+
+    /// Query any API endpoint, returning the raw JSON response.
+    ///
+    /// This is an escape hatch for endpoints that aren't covered by the typed API yet. The
+    /// authentication headers are sent as for any other request.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// // Search for "Let It Be"
+    /// let results = client
+    ///     .get_raw("catalog/search", &[("query", "Let It Be"), ("limit", "10")])
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn get_raw(&self, path: &str, params: &[(&str, &str)]) -> Result<Value, ApiError> {
+        Ok(self.do_request(path, params).await?)
     }
 
     async fn do_request<T: DeserializeOwned>(
@@ -323,22 +1713,116 @@ impl Client {
         path: &str,
         params: &[(&str, &str)],
     ) -> Result<T, reqwest::Error> {
-        do_request(&self.reqwest_client, path, params).await
+        do_request(
+            &self.reqwest_client,
+            &self.base_url,
+            path,
+            params,
+            self.request_timeout,
+            None,
+        )
+        .await?
+        .json()
+        .await
+    }
+
+    /// Query an endpoint like `do_request`, revalidating the cached response, if any, when the
+    /// response cache is enabled.
+    async fn do_cached_request<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> Result<T, ApiError> {
+        let Some(cache) = &self.response_cache else {
+            return Ok(self.do_request(path, params).await?);
+        };
+        let key = ResponseCache::key(path, params);
+        let cached = lock_cache(cache).get(&key);
+        let res = do_request(
+            &self.reqwest_client,
+            &self.base_url,
+            path,
+            params,
+            self.request_timeout,
+            cached.as_ref().map(|(etag, _)| etag.as_str()),
+        )
+        .await?;
+        let body = match cached {
+            Some((_, body)) if res.status() == reqwest::StatusCode::NOT_MODIFIED => body,
+            _ => {
+                let etag = res
+                    .headers()
+                    .get(reqwest::header::ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .map(str::to_string);
+                let body = res.bytes().await?;
+                match etag {
+                    Some(etag) => lock_cache(cache).insert(key, etag, body.clone()),
+                    // The cached response, if any, can't be revalidated anymore.
+                    None => lock_cache(cache).remove(&key),
+                }
+                body
+            }
+        };
+        Ok(serde_json::from_slice(&body)?)
     }
 }
 
-async fn do_request<T: DeserializeOwned>(
+/// Lock a cache, recovering it if another thread panicked while holding the lock: the caches
+/// are always left in a consistent state.
+fn lock_cache<C>(cache: &Mutex<C>) -> std::sync::MutexGuard<'_, C> {
+    cache
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Wrap a stream of bytes so that it ends with `ApiError::StreamTimeout` when no bytes are
+/// received for `timeout`.
+pub(crate) fn with_idle_timeout(
+    stream: impl Stream<Item = reqwest::Result<Bytes>>,
+    timeout: Duration,
+) -> impl Stream<Item = Result<Bytes, ApiError>> + Unpin {
+    Box::pin(stream::unfold(
+        Some(Box::pin(stream)),
+        move |stream| async move {
+            let mut stream = stream?;
+            match tokio::time::timeout(timeout, stream.next()).await {
+                Ok(Some(item)) => Some((item.map_err(ApiError::from), Some(stream))),
+                Ok(None) => None,
+                Err(_) => Some((Err(ApiError::StreamTimeout(timeout)), None)),
+            }
+        },
+    ))
+}
+
+/// Query an endpoint, returning the response if its status isn't an error.
+///
+/// If `etag` is given, it is sent in `If-None-Match` to revalidate a cached response, in which
+/// case the response may be a `304 Not Modified` without body.
+async fn do_request(
     client: &reqwest::Client,
+    base_url: &str,
     path: &str,
     params: &[(&str, &str)],
-) -> Result<T, reqwest::Error> {
-    let url = format!("{API_URL}{path}");
-    let res = client
-        .get(&url)
-        .query(params)
-        .send()
-        .await?
-        .error_for_status();
+    timeout: Duration,
+    etag: Option<&str>,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let url = format!("{base_url}{path}");
+    let mut request = client.get(&url).query(params).timeout(timeout);
+    if let Some(etag) = etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let start = Instant::now();
+    let res = request.send().await?;
+    tracing::debug!(
+        method = "GET",
+        path,
+        status = res.status().as_u16(),
+        latency_ms = start.elapsed().as_millis(),
+        revalidated = etag.is_some(),
+        "API request"
+    );
+    let res = res.error_for_status();
 
     #[cfg(test)]
     {
@@ -356,23 +1840,111 @@ async fn do_request<T: DeserializeOwned>(
         }
     }
 
-    res?.json().await
+    res
+}
+
+/// Compute the signature of a request: the MD5 hash of the endpoint path without slashes, the
+/// parameters sorted by key, the timestamp and the secret, all concatenated.
+fn compute_request_sig(
+    object_method: &str,
+    params: &[(&str, &str)],
+    timestamp: &str,
+    secret: &str,
+) -> String {
+    let mut params = params.to_vec();
+    params.sort_unstable_by_key(|(k, _)| *k);
+    let params: String = params.iter().map(|(k, v)| format!("{k}{v}")).collect();
+    format!(
+        "{:x}",
+        md5::compute(format!(
+            "{}{params}{timestamp}{secret}",
+            object_method.replace('/', "")
+        ))
+    )
 }
 
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ApiError {
     #[error("downloadable file is a sample")]
     IsSample,
     #[error("couldn't get key `{0}`")]
     MissingKey(String),
+    #[error("requested quality `{requested}` is unavailable, got `{delivered}`")]
+    QualityUnavailable {
+        requested: Quality,
+        delivered: Quality,
+    },
+    #[error("the server doesn't support streaming a range of the track")]
+    RangeNotSupported,
+    #[error("no data received while streaming for {0:?}")]
+    StreamTimeout(Duration),
+    #[error("couldn't derive a cover URL from `{0}`")]
+    InvalidCoverUrl(String),
+    #[error("the client isn't logged in")]
+    NotAuthenticated,
+    #[error("no default quality was set on the client")]
+    NoDefaultQuality,
     #[error("serde_json error `{0}`")]
     SerdeJsonError(#[from] serde_json::Error),
     #[error("reqwest error `{0}`")]
     ReqwestError(#[from] reqwest::Error),
 }
 
-fn make_http_client(app_id: &str, uat: Option<&str>) -> reqwest::Client {
-    let mut headers = reqwest::header::HeaderMap::new();
+/// Options of the HTTP requests sent by a `Client`, see `Client::new_with_http_options`.
+#[derive(Debug, Clone, Default)]
+pub struct HttpOptions {
+    user_agent: Option<String>,
+    headers: reqwest::header::HeaderMap,
+    base_url: Option<String>,
+}
+
+impl HttpOptions {
+    /// Set the user agent of requests, which defaults to the one of a web browser.
+    #[must_use]
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Send API requests, including the login request, to `base_url` instead of `API_URL`, e.g.
+    /// to test against a mock server. The URL should end with a slash, as request paths like
+    /// `album/get` are appended to it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use qobuz::HttpOptions;
+    /// let options = HttpOptions::default().base_url("http://localhost:8080/api.json/0.2/");
+    /// ```
+    #[must_use]
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Add a header to all requests.
+    ///
+    /// The headers required by Qobuz, i.e. `X-App-Id`, `X-User-Auth-Token` and `Content-Type`,
+    /// can't be overridden.
+    #[must_use]
+    pub fn header(
+        mut self,
+        name: reqwest::header::HeaderName,
+        value: reqwest::header::HeaderValue,
+    ) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// The base URL API requests are sent to.
+    fn api_url(&self) -> &str {
+        self.base_url.as_deref().unwrap_or(API_URL)
+    }
+}
+
+fn make_http_client(app_id: &str, uat: Option<&str>, options: &HttpOptions) -> reqwest::Client {
+    let mut headers = options.headers.clone();
     headers.insert("X-App-Id", app_id.parse().expect("Failed to parse app id"));
     headers.insert(
         reqwest::header::CONTENT_TYPE,
@@ -387,7 +1959,7 @@ fn make_http_client(app_id: &str, uat: Option<&str>) -> reqwest::Client {
         );
     }
     reqwest::ClientBuilder::new()
-        .user_agent(API_USER_AGENT)
+        .user_agent(options.user_agent.as_deref().unwrap_or(API_USER_AGENT))
         .default_headers(headers)
         .build()
         .expect("Couldn't build reqwest::Client")
@@ -400,6 +1972,156 @@ mod tests {
     use crate::test_utils::make_client;
     use tokio::test;
 
+    #[test]
+    async fn test_get_user_info() {
+        let user = make_client().await.get_user_info().unwrap();
+        assert!(user.max_quality().is_some());
+    }
+
+    #[test]
+    async fn test_compute_request_sig() {
+        let sig = compute_request_sig(
+            "track/getFileUrl",
+            &[
+                ("track_id", "64868955"),
+                ("format_id", "27"),
+                ("intent", "stream"),
+            ],
+            "1700000000",
+            "secret",
+        );
+        let expected = format!(
+            "{:x}",
+            md5::compute("trackgetFileUrlformat_id27intentstreamtrack_id648689551700000000secret")
+        );
+        assert_eq!(sig, expected);
+    }
+
+    #[test]
+    async fn test_get_artist_albums() {
+        let client = make_client().await;
+        let page = client
+            .get_artist_albums("26390", ReleaseType::Album, 0, 5)
+            .await
+            .unwrap();
+        assert!(page.items.len() <= 5);
+        assert!(!page.items.is_empty());
+        assert!(page.items.iter().all(|album| album
+            .release_type
+            .as_deref()
+            .and_then(ReleaseType::from_api_name)
+            == Some(ReleaseType::Album)));
+    }
+
+    #[test]
+    async fn test_get_item_page() {
+        let client = make_client().await;
+        let album: Album<WithExtra> = client.get_item_page("trrcz9pvaaz6b", 2, 3).await.unwrap();
+        assert_eq!(album.tracks.offset, 2);
+        assert_eq!(album.tracks.items.len(), 3);
+        let full_album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+        assert_eq!(album.tracks.items, full_album.tracks.items[2..5]);
+    }
+
+    #[test]
+    async fn test_tracks_by_disc() {
+        let client = make_client().await;
+        let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+        let discs = album.tracks_by_disc();
+        assert_eq!(
+            discs.values().map(Vec::len).sum::<usize>(),
+            album.tracks.items.len()
+        );
+        for (disc, tracks) in &discs {
+            assert!(tracks.iter().all(|track| track.media_number == *disc));
+            assert!(tracks
+                .windows(2)
+                .all(|pair| pair[0].track_number <= pair[1].track_number));
+        }
+    }
+
+    #[test]
+    async fn test_get_all_artist_tracks() {
+        let client = make_client().await;
+        let tracks = client.get_all_artist_tracks("26390").await.unwrap();
+        assert!(!tracks.is_empty());
+        let with_isrc: Vec<&str> = tracks
+            .iter()
+            .map(|track| track.isrc.as_str())
+            .filter(|isrc| !isrc.is_empty())
+            .collect();
+        let isrcs: HashSet<&str> = with_isrc.iter().copied().collect();
+        assert_eq!(isrcs.len(), with_isrc.len());
+    }
+
+    #[test]
+    async fn test_base_url() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let body = r#"{"message":"Invalid username/email and password combination"}"#;
+            socket
+                .write_all(
+                    format!(
+                        "HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+        let credentials = Credentials::from_plaintext("user@example.com", "password", "123", "");
+        let options = HttpOptions::default().base_url(format!("http://{addr}/api/"));
+        let res = Client::new_with_http_options(credentials, &options).await;
+        assert!(matches!(res, Err(LoginError::InvalidCredentials)));
+        assert!(server.await.unwrap().starts_with("GET /api/user/login?"));
+    }
+
+    #[test]
+    async fn test_get_track_with_full_album() {
+        let client = make_client().await;
+        let (track, album) = client.get_track_with_full_album("129342731").await.unwrap();
+        assert_eq!(album.id, track.album.id);
+        assert!(album.tracks.items.iter().any(|t| t.id == track.id));
+    }
+
+    #[test]
+    async fn test_public_client() {
+        let app_id = Credentials::from_env().unwrap().app_id;
+        let client = Client::public(&app_id);
+        assert!(!client.is_authenticated());
+        let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+        assert_eq!(album.id, "trrcz9pvaaz6b");
+        assert!(matches!(
+            client.get_track_file("129342731", Quality::Mp3).await,
+            Err(ApiError::NotAuthenticated)
+        ));
+        assert!(matches!(
+            client.get_user_favorites::<Album<WithoutExtra>>().await,
+            Err(ApiError::NotAuthenticated)
+        ));
+    }
+
+    #[test]
+    async fn test_clamp_quality() {
+        let client = make_client().await;
+        let max = client.max_allowed_quality().unwrap();
+        assert_eq!(client.clamp_quality(Quality::HiRes192), max);
+        assert_eq!(client.clamp_quality(Quality::Mp3), Quality::Mp3);
+    }
+
+    #[test]
+    #[ignore = "logging out may invalidate the session of the other tests using the same account"]
+    async fn test_logout() {
+        make_client().await.logout().await.unwrap();
+    }
+
     #[test]
     async fn test_get_user_favorites() {
         let client = make_client().await;
@@ -417,6 +2139,62 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    async fn test_get_user_favorites_stream() {
+        let client = make_client().await;
+        let favorites = client
+            .get_user_favorites::<Track<WithExtra>>()
+            .await
+            .unwrap();
+        let streamed: Vec<Track<WithExtra>> = client
+            .get_user_favorites_stream()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(favorites, streamed);
+    }
+
+    #[test]
+    async fn test_get_page() {
+        let client = make_client().await;
+        let favorites = client
+            .get_user_favorites::<Track<WithExtra>>()
+            .await
+            .unwrap();
+        let mut paged: Vec<Track<WithExtra>> = vec![];
+        let mut offset = Some(0);
+        while let Some(o) = offset {
+            let page: Array<Track<WithExtra>> = client
+                .get_page(
+                    "favorite/getUserFavorites",
+                    &[("type", "tracks")],
+                    "tracks",
+                    o,
+                    10,
+                )
+                .await
+                .unwrap();
+            offset = page.next_offset();
+            paged.extend(page.items);
+        }
+        assert_eq!(favorites, paged);
+    }
+
+    #[test]
+    async fn test_is_favorite() {
+        let client = make_client().await;
+        let favorites = client
+            .get_user_favorites::<Track<WithExtra>>()
+            .await
+            .unwrap();
+        if let Some(track) = favorites.first() {
+            assert!(client
+                .is_favorite::<Track<WithExtra>>(&track.id.to_string())
+                .await
+                .unwrap());
+        }
+    }
+
     #[test]
     async fn test_get_user_playlists() {
         let client = make_client().await;
@@ -425,12 +2203,121 @@ mod tests {
 
     #[test]
     async fn test_get_track_file_url() {
+        let client = make_client().await;
         let track_id = "64868955";
-        make_client()
-            .await
+        client
             .get_track_file_url(track_id, Quality::HiRes96)
             .await
             .unwrap();
+        client
+            .get_track_file_url(64_868_955, Quality::HiRes96)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    async fn test_get_track_file_url_with_expiry() {
+        let client = make_client().await;
+        let (url, expiry) = client
+            .get_track_file_url_with_expiry("64868955", Quality::Cd)
+            .await
+            .unwrap();
+        assert!(url.query_pairs().any(|(key, _)| key == "etsp"));
+        assert!(expiry.unwrap() > Utc::now());
+    }
+
+    #[test]
+    async fn test_get_track_credits() {
+        let credits = make_client()
+            .await
+            .get_track_credits("129342731")
+            .await
+            .unwrap();
+        assert!(credits.iter().any(|credit| credit.role == "Composer"));
+    }
+
+    #[test]
+    async fn test_estimate_download_size() {
+        let client = make_client().await;
+        let album = client.get_album("lz75qrx8pnjac").await.unwrap();
+        let tracks = &album.tracks.items;
+        let exact = client
+            .estimate_download_size(tracks, Quality::Mp3, SizeEstimate::Exact)
+            .await
+            .unwrap();
+        let estimated = client
+            .estimate_download_size(tracks, Quality::Mp3, SizeEstimate::FromDuration)
+            .await
+            .unwrap();
+        // MP3 files have a constant bitrate, so the estimate should be close.
+        assert!(exact.abs_diff(estimated) < exact / 10);
+    }
+
+    #[test]
+    async fn test_estimate_download_size_skips_unavailable() {
+        let client = Client::public("000000000");
+        let mut track: Track<WithExtra> = crate::test_utils::fixture("track");
+        track.streamable = false;
+        let tracks = [track];
+        // No request is made for unavailable tracks, so this works offline.
+        for mode in [SizeEstimate::Exact, SizeEstimate::FromDuration] {
+            let size = client
+                .estimate_download_size(&tracks, Quality::Cd, mode)
+                .await
+                .unwrap();
+            assert_eq!(size, 0);
+        }
+    }
+
+    #[test]
+    async fn test_get_featured_playlists() {
+        let client = make_client().await;
+        let playlists = client.get_featured_playlists(None, 5).await.unwrap();
+        assert!(!playlists.is_empty());
+        assert!(playlists.len() <= 5);
+        client
+            .get_featured_playlists(Some("hi-res"), 5)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    async fn test_check_track_availability() {
+        let availability = make_client()
+            .await
+            .check_track_availability("64868955", Quality::Mp3)
+            .await
+            .unwrap();
+        assert_eq!(availability, Availability::Available);
+    }
+
+    #[test]
+    async fn test_track_file_cache() {
+        let client = make_client().await.with_track_file_cache(10);
+        let file = client
+            .get_track_file("64868955", Quality::Mp3)
+            .await
+            .unwrap();
+        let cached = client
+            .get_track_file("64868955", Quality::Mp3)
+            .await
+            .unwrap();
+        assert_eq!(file, cached);
+    }
+
+    #[test]
+    async fn test_get_track_file_url_default() {
+        let client = make_client().await;
+        let track_id = "64868955";
+        assert!(matches!(
+            client.get_track_file_url_default(track_id).await,
+            Err(ApiError::NoDefaultQuality)
+        ));
+        client
+            .with_default_quality(Quality::Cd)
+            .get_track_file_url_default(track_id)
+            .await
+            .unwrap();
     }
 
     #[test]
@@ -441,6 +2328,66 @@ mod tests {
         client.get_track("no").await.unwrap_err();
     }
 
+    #[test]
+    async fn test_get_track_with() {
+        let client = make_client().await;
+        let track = client.get_track("64868955").await.unwrap();
+        let without_album = client
+            .get_track_with::<WithoutExtra>("64868955")
+            .await
+            .unwrap();
+        assert_eq!(without_album, track.without_extra());
+    }
+
+    #[test]
+    async fn test_track_maximum_quality() {
+        let client = make_client().await;
+        // Creedence Clearwater Revival - Lodi, available in Hi-Res 192 kHz
+        let track = client.get_track("18893849").await.unwrap();
+        assert_eq!(track.maximum_bit_depth, Some(24));
+        assert_eq!(
+            track.maximum_sampling_rate,
+            Some(types::SamplingRate(192.0))
+        );
+    }
+
+    #[test]
+    async fn test_get_track_by_isrc() {
+        let client = make_client().await;
+        let track = client.get_track("64868955").await.unwrap();
+        let found = client.search_tracks_by_isrc(&track.isrc).await.unwrap();
+        assert!(found.iter().any(|t| t.id == track.id));
+        assert!(client
+            .get_track_by_isrc("XX0000000000")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    async fn test_get_album_by_upc() {
+        let client = make_client().await;
+        let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+        let found = client.search_albums_by_upc(&album.upc).await.unwrap();
+        assert!(found.iter().any(|a| a.id == album.id));
+    }
+
+    #[test]
+    async fn test_get_tracks() {
+        let client = make_client().await;
+        let track_ids = ["64868955", "18893849"];
+        let (tracks, missing) = client.get_tracks(&track_ids).await.unwrap();
+        assert_eq!(
+            tracks.iter().map(|t| t.id.to_string()).collect::<Vec<_>>(),
+            track_ids
+        );
+        assert!(missing.is_empty());
+        let (tracks, missing) = client.get_tracks(&["999999999", "64868955"]).await.unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].id, 64_868_955);
+        assert_eq!(missing, ["999999999"]);
+    }
+
     #[test]
     async fn test_get_album() {
         let client = make_client().await;
@@ -466,6 +2413,44 @@ mod tests {
         // TODO: First user playlist
     }
 
+    #[test]
+    async fn test_download_cover() {
+        let client = make_client().await;
+        let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+        let large = client
+            .download_cover(&album, CoverSize::Large)
+            .await
+            .unwrap();
+        let max = client.download_cover(&album, CoverSize::Max).await.unwrap();
+        assert!(max.len() > large.len());
+    }
+
+    #[test]
+    async fn test_get_playlist_tracks() {
+        let client = make_client().await;
+        let playlist_id = "1141084"; // Official Qobuz playlist
+        let playlist = client.get_playlist(playlist_id).await.unwrap();
+        let tracks = client
+            .get_playlist_tracks(playlist_id, 0, 10)
+            .await
+            .unwrap();
+        assert_eq!(tracks.total, playlist.tracks.total);
+        assert_eq!(
+            tracks.items[..],
+            playlist.tracks.items[..tracks.items.len()]
+        );
+    }
+
+    #[test]
+    async fn test_get_raw() {
+        let res = make_client()
+            .await
+            .get_raw("track/get", &[("track_id", "64868955")])
+            .await
+            .unwrap();
+        assert_eq!(res.get("id"), Some(&Value::from(64_868_955)));
+    }
+
     #[test]
     async fn test_stream_track() {
         use futures::StreamExt;
@@ -476,4 +2461,17 @@ mod tests {
             .unwrap();
         assert!(stream.next().await.is_some());
     }
+
+    #[test]
+    async fn test_stream_track_range() {
+        use futures::TryStreamExt;
+        let client = make_client().await;
+        let (stream, total) = client
+            .stream_track_range("64868955", Quality::Mp3, 100, Some(199))
+            .await
+            .unwrap();
+        let bytes: Vec<Bytes> = stream.try_collect().await.unwrap();
+        assert_eq!(bytes.iter().map(Bytes::len).sum::<usize>(), 100);
+        assert!(total > 200);
+    }
 }
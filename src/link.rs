@@ -0,0 +1,186 @@
+use thiserror::Error;
+use url::Url;
+
+/// Hosts serving Qobuz item pages.
+pub const QOBUZ_HOSTS: [&str; 3] = ["play.qobuz.com", "open.qobuz.com", "www.qobuz.com"];
+
+/// The kind of item a Qobuz URL points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    Track,
+    Album,
+    Playlist,
+    Artist,
+}
+
+impl ItemKind {
+    fn from_path_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "track" => Some(Self::Track),
+            "album" => Some(Self::Album),
+            "playlist" => Some(Self::Playlist),
+            // www.qobuz.com uses `interpreter` for artist pages
+            "artist" | "interpreter" => Some(Self::Artist),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a Qobuz URL, returning the kind and ID of the item it points to.
+///
+/// The kind keyword may be preceded by a region (e.g. `/us-en/album/...`), and the ID is the last
+/// segment of the path after the keyword, which can be preceded by a slug, either as its own
+/// segment or glued to the ID (see `parse_slug_id`).
+///
+/// # Example
+///
+/// ```
+/// use qobuz::link::{parse_url, ItemKind};
+/// let (kind, id) =
+///     parse_url("https://www.qobuz.com/us-en/album/abbey-road-the-beatles/trrcz9pvaaz6b").unwrap();
+/// assert_eq!(kind, ItemKind::Album);
+/// assert_eq!(id, "trrcz9pvaaz6b");
+/// ```
+pub fn parse_url(url: &str) -> Result<(ItemKind, String), ParseUrlError> {
+    let url = Url::parse(url)?;
+    let host = url.host_str().unwrap_or_default();
+    if !QOBUZ_HOSTS.contains(&host) {
+        return Err(ParseUrlError::UnknownHost(host.to_string()));
+    }
+    let mut segments = url
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty());
+    let kind = segments
+        .find_map(ItemKind::from_path_segment)
+        .ok_or(ParseUrlError::UnrecognizedKind)?;
+    let id = segments.next_back().ok_or(ParseUrlError::MissingId)?;
+    Ok((kind, parse_slug_id(id)?.to_string()))
+}
+
+/// Extract the ID of an item from a slug it is glued to, e.g. `let-it-be-129342731`, as found in
+/// shared links and some API responses. A bare ID is returned as is.
+///
+/// IDs are alphanumeric, e.g. album IDs like `trrcz9pvaaz6b`, so the ID is everything after the
+/// last hyphen.
+///
+/// # Example
+///
+/// ```
+/// use qobuz::link::parse_slug_id;
+/// assert_eq!(parse_slug_id("let-it-be-129342731").unwrap(), "129342731");
+/// assert_eq!(parse_slug_id("abbey-road-trrcz9pvaaz6b").unwrap(), "trrcz9pvaaz6b");
+/// assert_eq!(parse_slug_id("trrcz9pvaaz6b").unwrap(), "trrcz9pvaaz6b");
+/// ```
+pub fn parse_slug_id(slug: &str) -> Result<&str, ParseUrlError> {
+    let id = slug.trim().rsplit('-').next().unwrap_or_default();
+    if id.is_empty() {
+        return Err(ParseUrlError::MissingId);
+    }
+    if !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(ParseUrlError::InvalidId(id.to_string()));
+    }
+    Ok(id)
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ParseUrlError {
+    #[error("invalid URL `{0}`")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("unknown host `{0}`")]
+    UnknownHost(String),
+    #[error("couldn't find the kind of item in the URL")]
+    UnrecognizedKind,
+    #[error("couldn't find the ID of the item in the URL")]
+    MissingId,
+    #[error("invalid item ID `{0}`")]
+    InvalidId(String),
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let cases = [
+            (
+                "https://play.qobuz.com/track/64868955",
+                ItemKind::Track,
+                "64868955",
+            ),
+            (
+                "https://open.qobuz.com/album/trrcz9pvaaz6b",
+                ItemKind::Album,
+                "trrcz9pvaaz6b",
+            ),
+            (
+                "https://open.qobuz.com/playlist/1141084/",
+                ItemKind::Playlist,
+                "1141084",
+            ),
+            (
+                "https://www.qobuz.com/us-en/album/abbey-road-the-beatles/trrcz9pvaaz6b",
+                ItemKind::Album,
+                "trrcz9pvaaz6b",
+            ),
+            (
+                "https://www.qobuz.com/fr-fr/interpreter/the-beatles/26390",
+                ItemKind::Artist,
+                "26390",
+            ),
+        ];
+        for (url, kind, id) in cases {
+            assert_eq!(parse_url(url).unwrap(), (kind, id.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_parse_slug_id() {
+        let cases = [
+            ("let-it-be-129342731", "129342731"),
+            ("129342731", "129342731"),
+            ("abbey-road-remastered-trrcz9pvaaz6b", "trrcz9pvaaz6b"),
+            ("trrcz9pvaaz6b", "trrcz9pvaaz6b"),
+            ("0060254782012", "0060254782012"),
+        ];
+        for (slug, id) in cases {
+            assert_eq!(parse_slug_id(slug).unwrap(), id);
+        }
+        assert!(matches!(
+            parse_slug_id("let-it-be-"),
+            Err(ParseUrlError::MissingId)
+        ));
+        assert!(matches!(
+            parse_slug_id("let-it-be-12345.html"),
+            Err(ParseUrlError::InvalidId(_))
+        ));
+        assert_eq!(
+            parse_url("https://www.qobuz.com/us-en/album/abbey-road-trrcz9pvaaz6b").unwrap(),
+            (ItemKind::Album, "trrcz9pvaaz6b".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_url() {
+        assert!(matches!(
+            parse_url("https://example.com/track/64868955"),
+            Err(ParseUrlError::UnknownHost(_))
+        ));
+        assert!(matches!(
+            parse_url("https://www.qobuz.com/us-en/discover"),
+            Err(ParseUrlError::UnrecognizedKind)
+        ));
+        assert!(matches!(
+            parse_url("https://open.qobuz.com/track/"),
+            Err(ParseUrlError::MissingId)
+        ));
+        assert!(matches!(
+            parse_url("not a url"),
+            Err(ParseUrlError::InvalidUrl(_))
+        ));
+    }
+}
@@ -0,0 +1,331 @@
+//! A mock of the Qobuz API serving recorded responses, to test code using this crate without
+//! network access or credentials.
+//!
+//! The server answers the login request and serves fixtures for the `MOCK_ALBUM_ID` album, the
+//! `MOCK_TRACK_ID` track and the `MOCK_PLAYLIST_ID` playlist. Other responses can be added with
+//! `MockServer::set_response`. Responses are sent with an `ETag`, and requests sending it back in
+//! `If-None-Match` get a `304 Not Modified` response, as with `Client::with_response_cache`.
+//!
+//! # Example
+//!
+//! ```
+//! # tokio_test::block_on(async {
+//! use qobuz::mock::{MockServer, MOCK_ALBUM_ID};
+//! let server = MockServer::start().await.unwrap();
+//! let client = server.client().await.unwrap();
+//! let album = client.get_album(MOCK_ALBUM_ID).await.unwrap();
+//! assert_eq!(album.title, "Mock Album");
+//! # })
+//! ```
+
+use crate::{
+    auth::{Credentials, LoginError},
+    Client, HttpOptions,
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// ID of the album served by `MockServer`.
+pub const MOCK_ALBUM_ID: &str = "mockalbum001";
+/// ID of the track served by `MockServer`.
+pub const MOCK_TRACK_ID: &str = "4000001";
+/// ID of the playlist served by `MockServer`.
+pub const MOCK_PLAYLIST_ID: &str = "6000001";
+
+const LOGIN_FIXTURE: &str = include_str!("fixtures/login.json");
+const ALBUM_FIXTURE: &str = include_str!("fixtures/album.json");
+const TRACK_FIXTURE: &str = include_str!("fixtures/track.json");
+const PLAYLIST_FIXTURE: &str = include_str!("fixtures/playlist.json");
+
+type Responses = Arc<Mutex<HashMap<String, Value>>>;
+
+/// A local HTTP server mocking the Qobuz API, stopped when dropped.
+///
+/// Responses are looked up by request path only, e.g. `album/get`, so the same response is
+/// served whatever the query parameters. Unknown paths get a 404 response.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    responses: Responses,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Start a server on a free local port, serving the recorded fixtures.
+    pub async fn start() -> Result<Self, std::io::Error> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let responses: Responses = Arc::default();
+        for (path, fixture) in [
+            ("user/login", LOGIN_FIXTURE),
+            ("album/get", ALBUM_FIXTURE),
+            ("track/get", TRACK_FIXTURE),
+            ("playlist/get", PLAYLIST_FIXTURE),
+        ] {
+            let response = serde_json::from_str(fixture).expect("Fixtures should be valid JSON");
+            lock(&responses).insert(path.to_string(), response);
+        }
+        let task = tokio::spawn(serve(listener, responses.clone()));
+        Ok(Self {
+            addr,
+            responses,
+            task,
+        })
+    }
+
+    /// Serve `response` for requests to `path`, e.g. `track/getFileUrl`, replacing any previous
+    /// response.
+    pub fn set_response(&self, path: &str, response: Value) {
+        lock(&self.responses).insert(path.to_string(), response);
+    }
+
+    /// The base URL of the mocked API, see `HttpOptions::base_url`.
+    #[must_use]
+    pub fn base_url(&self) -> String {
+        format!("http://{}/api.json/0.2/", self.addr)
+    }
+
+    /// `HttpOptions` sending requests to this server.
+    #[must_use]
+    pub fn http_options(&self) -> HttpOptions {
+        HttpOptions::default().base_url(self.base_url())
+    }
+
+    /// Create a `Client` logged in to this server with dummy credentials.
+    pub async fn client(&self) -> Result<Client, LoginError> {
+        let credentials =
+            Credentials::from_plaintext("mock@example.com", "password", "000000000", "secret");
+        Client::new_with_http_options(credentials, &self.http_options()).await
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn lock(
+    responses: &Mutex<HashMap<String, Value>>,
+) -> std::sync::MutexGuard<'_, HashMap<String, Value>> {
+    responses
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+async fn serve(listener: TcpListener, responses: Responses) {
+    while let Ok((socket, _)) = listener.accept().await {
+        tokio::spawn(handle_connection(socket, responses.clone()));
+    }
+}
+
+/// Answer a single request, closing the connection afterwards.
+async fn handle_connection(mut socket: TcpStream, responses: Responses) {
+    let mut request = Vec::new();
+    let mut buf = [0; 4096];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let if_none_match = request.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("if-none-match")
+            .then(|| value.trim().to_string())
+    });
+    // The request line looks like `GET /api.json/0.2/album/get?album_id=... HTTP/1.1`.
+    let path = request
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches("/api.json/0.2/");
+    let response = lock(&responses).get(path).cloned();
+    let (status, body) = match response {
+        Some(response) => ("200 OK", response.to_string()),
+        None => (
+            "404 Not Found",
+            serde_json::json!({
+                "status": "error",
+                "code": 404,
+                "message": format!("No mock response for `{path}`"),
+            })
+            .to_string(),
+        ),
+    };
+    let etag = format!("\"{:x}\"", md5::compute(&body));
+    let (status, body) = if status == "200 OK" && if_none_match.as_ref() == Some(&etag) {
+        ("304 Not Modified", String::new())
+    } else {
+        (status, body)
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nETag: {etag}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    // The client may have given up on the request, in which case there's nobody to answer.
+    let _ = socket.write_all(response.as_bytes()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::ApiError;
+    use tokio::test;
+
+    #[test]
+    async fn test_fixtures() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client().await.unwrap();
+        assert_eq!(client.get_user_info().unwrap().display_name, "Mock User");
+
+        let album = client.get_album(MOCK_ALBUM_ID).await.unwrap();
+        assert_eq!(album.id, MOCK_ALBUM_ID);
+        assert_eq!(album.tracks.items.len(), 2);
+
+        let track = client.get_track(MOCK_TRACK_ID).await.unwrap();
+        assert_eq!(track.id.to_string(), MOCK_TRACK_ID);
+        assert_eq!(track.album.id, MOCK_ALBUM_ID);
+
+        let playlist = client.get_playlist(MOCK_PLAYLIST_ID).await.unwrap();
+        assert_eq!(playlist.id.to_string(), MOCK_PLAYLIST_ID);
+        assert_eq!(playlist.tracks.items[0].id, track.id);
+    }
+
+    #[test]
+    async fn test_public_client() {
+        let server = MockServer::start().await.unwrap();
+        let client = Client::public_with_http_options("000000000", &server.http_options());
+        assert!(!client.is_authenticated());
+        let album = client.get_album(MOCK_ALBUM_ID).await.unwrap();
+        assert_eq!(album.id, MOCK_ALBUM_ID);
+        assert!(matches!(
+            client
+                .get_track_file(MOCK_TRACK_ID, crate::quality::Quality::Cd)
+                .await,
+            Err(ApiError::NotAuthenticated)
+        ));
+    }
+
+    #[test]
+    async fn test_set_response() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client().await.unwrap();
+        assert!(matches!(
+            client.get_artist("2000001").await,
+            Err(ApiError::ReqwestError(e)) if e.status() == Some(reqwest::StatusCode::NOT_FOUND)
+        ));
+        let mut artist: Value = serde_json::from_str(ALBUM_FIXTURE).unwrap();
+        artist = artist["artist"].clone();
+        artist["tracks"] = serde_json::json!({"items": [], "limit": 500, "offset": 0, "total": 0});
+        artist["albums"] = serde_json::json!({"items": [], "limit": 500, "offset": 0, "total": 0});
+        server.set_response("artist/get", artist);
+        let artist = client.get_artist("2000001").await.unwrap();
+        assert_eq!(artist.name, "Mock Artist");
+    }
+
+    #[test]
+    async fn test_get_artist_albums() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client().await.unwrap();
+        let album: Value = serde_json::from_str(ALBUM_FIXTURE).unwrap();
+        let albums: Vec<Value> = [Some("album"), Some("live"), None]
+            .into_iter()
+            .map(|release_type| {
+                let mut album = album.clone();
+                album["release_type"] = release_type.into();
+                album
+            })
+            .collect();
+        server.set_response(
+            "artist/get",
+            serde_json::json!({
+                "albums": {"items": albums, "limit": 3, "offset": 0, "total": 3}
+            }),
+        );
+        let page = client
+            .get_artist_albums("2000001", crate::types::ReleaseType::Live, 0, 3)
+            .await
+            .unwrap();
+        let release_types: Vec<_> = page
+            .items
+            .iter()
+            .map(|album| album.release_type.as_deref())
+            .collect();
+        assert_eq!(release_types, [Some("live"), None]);
+    }
+
+    #[test]
+    async fn test_logout() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client().await.unwrap();
+        server.set_response("user/logout", serde_json::json!({"status": "success"}));
+        client.logout().await.unwrap();
+        let public = Client::public_with_http_options("000000000", &server.http_options());
+        assert!(matches!(
+            public.logout().await,
+            Err(ApiError::NotAuthenticated)
+        ));
+    }
+
+    #[test]
+    async fn test_get_album_walks_pages() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client().await.unwrap();
+        // The server ignores the offset, so each page has the same two tracks.
+        let mut album: Value = serde_json::from_str(ALBUM_FIXTURE).unwrap();
+        album["tracks"]["total"] = 4.into();
+        server.set_response("album/get", album);
+        let album = client.get_album(MOCK_ALBUM_ID).await.unwrap();
+        assert_eq!(album.tracks.items.len(), 4);
+        assert!(!album.tracks.has_more());
+    }
+
+    #[test]
+    async fn test_response_cache() {
+        let server = MockServer::start().await.unwrap();
+        let client = server.client().await.unwrap().with_response_cache(10);
+        let album = client.get_album(MOCK_ALBUM_ID).await.unwrap();
+        assert_eq!(client.get_album(MOCK_ALBUM_ID).await.unwrap(), album);
+
+        // Replace the cached body while keeping its ETag: as the album didn't change on the
+        // server, the cached body is reused.
+        let key = crate::cache::ResponseCache::key(
+            "album/get",
+            &[
+                ("album_id", MOCK_ALBUM_ID),
+                ("extra", ""),
+                ("limit", "500"),
+                ("offset", "0"),
+            ],
+        );
+        let cache = client.response_cache.as_ref().unwrap();
+        let (etag, body) = cache.lock().unwrap().get(&key).unwrap();
+        let mut cached: Value = serde_json::from_slice(&body).unwrap();
+        cached["title"] = "Cached Album".into();
+        let cached = serde_json::to_vec(&cached).unwrap().into();
+        cache.lock().unwrap().insert(key, etag, cached);
+        let album = client.get_album(MOCK_ALBUM_ID).await.unwrap();
+        assert_eq!(album.title, "Cached Album");
+
+        let mut changed: Value = serde_json::from_str(ALBUM_FIXTURE).unwrap();
+        changed["title"] = "Changed Album".into();
+        server.set_response("album/get", changed);
+        let album = client.get_album(MOCK_ALBUM_ID).await.unwrap();
+        assert_eq!(album.title, "Changed Album");
+    }
+}
@@ -1,17 +1,41 @@
 use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+/// A quality of track files. Qualities are ordered from the lowest to the highest.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(try_from = "u8")]
 #[serde(into = "u8")]
 pub enum Quality {
+    /// MP3 at a constant 320 kbps. Qobuz doesn't serve other MP3 bitrates, format ID 5 being its
+    /// only lossy format.
     Mp3,
     Cd,
     HiRes96,
     HiRes192,
 }
 
+impl Quality {
+    /// The bitrate files are delivered at in kbps, e.g. to show "320 kbps", or `None` for
+    /// lossless qualities, whose bitrate depends on the track.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use qobuz::quality::Quality;
+    /// assert_eq!(Quality::Mp3.bitrate_hint(), Some(320));
+    /// assert_eq!(Quality::Cd.bitrate_hint(), None);
+    /// ```
+    #[must_use]
+    pub const fn bitrate_hint(&self) -> Option<u32> {
+        match self {
+            Self::Mp3 => Some(320),
+            Self::Cd | Self::HiRes96 | Self::HiRes192 => None,
+        }
+    }
+}
+
 impl Display for Quality {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -40,6 +64,32 @@ impl TryFrom<u8> for Quality {
 #[error("Invalid quality `{0}`")]
 pub struct InvalidQualityError(u8);
 
+impl FromStr for Quality {
+    type Err = ParseQualityError;
+    /// Parse a quality from its name (`mp3`, `cd`, `hires96` or `hires192`, case-insensitive) or
+    /// its Qobuz format ID. `lossless` and `hires` are also accepted for `cd` and `hires192`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mp3" => Ok(Self::Mp3),
+            "cd" | "lossless" => Ok(Self::Cd),
+            "hires96" => Ok(Self::HiRes96),
+            "hires192" | "hires" => Ok(Self::HiRes192),
+            v => v
+                .parse::<u8>()
+                .ok()
+                .and_then(|id| Self::try_from(id).ok())
+                .ok_or_else(|| ParseQualityError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "Invalid quality `{0}`, expected one of mp3, cd (or lossless), hires96, hires192 (or hires) or \
+     a format ID"
+)]
+pub struct ParseQualityError(String);
+
 impl From<Quality> for u8 {
     fn from(val: Quality) -> Self {
         match val {
@@ -74,3 +124,33 @@ impl From<&Quality> for FileExtension {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        let cases = [
+            ("mp3", Quality::Mp3),
+            ("MP3", Quality::Mp3),
+            ("5", Quality::Mp3),
+            ("cd", Quality::Cd),
+            ("lossless", Quality::Cd),
+            ("6", Quality::Cd),
+            ("hires96", Quality::HiRes96),
+            ("HiRes96", Quality::HiRes96),
+            ("7", Quality::HiRes96),
+            ("hires192", Quality::HiRes192),
+            ("hires", Quality::HiRes192),
+            ("27", Quality::HiRes192),
+        ];
+        for (s, quality) in cases {
+            assert_eq!(s.parse::<Quality>().unwrap(), quality, "{s}");
+        }
+        let error = "flac".parse::<Quality>().unwrap_err();
+        assert!(error.to_string().contains("`flac`"));
+        assert!("8".parse::<Quality>().is_err());
+    }
+}
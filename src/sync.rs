@@ -0,0 +1,90 @@
+use std::{collections::HashSet, hash::Hash};
+
+/// The changes needed to make a local library match the user's favorites, see `diff_favorites`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FavoritesDiff<'a, T, I> {
+    /// Favorites missing from the local library, in the order of the favorites.
+    pub to_add: Vec<&'a T>,
+    /// IDs of local items which aren't favorites anymore, in the order of the local IDs.
+    pub to_remove: Vec<I>,
+}
+
+impl<T, I> FavoritesDiff<'_, T, I> {
+    /// Whether the local library already matches the favorites.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.to_add.is_empty() && self.to_remove.is_empty()
+    }
+}
+
+/// Compare the IDs of the items of a local library with the user's favorites, as returned by
+/// `Client::get_user_favorites`, using `id` to get the ID of a favorite.
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # use qobuz::{auth::Credentials, Client};
+/// # let credentials = Credentials::from_env().unwrap();
+/// # let client = Client::new(credentials).await.unwrap();
+/// use qobuz::{sync::diff_favorites, types::{extra::WithoutExtra, Album}};
+/// // IDs of the albums already downloaded, e.g. "Abbey Road"
+/// let local = vec!["trrcz9pvaaz6b".to_string()];
+/// let favorites = client
+///     .get_user_favorites::<Album<WithoutExtra>>()
+///     .await
+///     .unwrap();
+/// let diff = diff_favorites(&local, &favorites, |album| album.id.clone());
+/// for album in diff.to_add {
+///     println!("To download: {album}");
+/// }
+/// for id in diff.to_remove {
+///     println!("To delete: {id}");
+/// }
+/// # })
+/// ```
+pub fn diff_favorites<'a, T, I>(
+    local: &[I],
+    remote: &'a [T],
+    id: impl Fn(&T) -> I,
+) -> FavoritesDiff<'a, T, I>
+where
+    I: Eq + Hash + Clone,
+{
+    let local_ids: HashSet<&I> = local.iter().collect();
+    let remote_ids: HashSet<I> = remote.iter().map(&id).collect();
+    FavoritesDiff {
+        to_add: remote
+            .iter()
+            .filter(|item| !local_ids.contains(&id(item)))
+            .collect(),
+        to_remove: local
+            .iter()
+            .filter(|local_id| !remote_ids.contains(*local_id))
+            .cloned()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_favorites() {
+        let local = [1, 2, 3];
+        let remote = [(4, "d"), (2, "b"), (5, "e")];
+        let diff = diff_favorites(&local, &remote, |(id, _)| *id);
+        assert_eq!(diff.to_add, [&(4, "d"), &(5, "e")]);
+        assert_eq!(diff.to_remove, [1, 3]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_favorites_in_sync() {
+        let local = ["a".to_string(), "b".to_string()];
+        let remote = ["b", "a"];
+        let diff = diff_favorites(&local, &remote, |id| (*id).to_string());
+        assert!(diff.is_empty());
+    }
+}
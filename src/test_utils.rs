@@ -1,4 +1,5 @@
 use crate::{auth::Credentials, downloader::Downloader, Client};
+use serde::de::DeserializeOwned;
 use std::path::Path;
 
 pub async fn make_client() -> Client {
@@ -13,3 +14,13 @@ pub async fn make_client_and_downloader() -> (Client, Downloader) {
     let client = make_client().await;
     (client.clone(), Downloader::new(client, Path::new("music")))
 }
+
+/// Parse the recorded response `name`, e.g. `album`, from the fixtures of the `mock` module.
+pub fn fixture<T: DeserializeOwned>(name: &str) -> T {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/mock/fixtures")
+        .join(format!("{name}.json"));
+    let json = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Couldn't read fixture {}: {e}", path.display()));
+    serde_json::from_str(&json).expect("Fixtures should be valid JSON")
+}
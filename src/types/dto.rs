@@ -0,0 +1,121 @@
+//! Flat representations of Qobuz items, with a stable JSON schema independent of the Qobuz API.
+//!
+//! # Example
+//!
+//! ```
+//! # tokio_test::block_on(async {
+//! # use qobuz::{auth::Credentials, Client};
+//! # let credentials = Credentials::from_env().unwrap();
+//! # let client = Client::new(credentials).await.unwrap();
+//! use qobuz::types::dto::AlbumDto;
+//! // Export "Abbey Road" as JSON
+//! let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+//! let json = serde_json::to_string(&AlbumDto::from(&album)).unwrap();
+//! # })
+//! ```
+
+use super::{
+    extra::{ExtraFlag, WithExtra, WithoutExtra},
+    Album, Array, Track,
+};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// A track along with the main information on its album.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TrackDto {
+    pub id: u64,
+    pub title: String,
+    pub version: Option<String>,
+    pub artist: Option<String>,
+    pub album_id: String,
+    pub album_title: String,
+    pub album_artist: String,
+    pub media_number: i64,
+    pub track_number: u64,
+    pub duration_secs: u64,
+    pub isrc: String,
+    pub release_date: NaiveDate,
+    pub explicit: bool,
+    pub hires: bool,
+    pub streamable: bool,
+}
+
+impl TrackDto {
+    fn new<EF1, EF2>(track: &Track<EF1>, album: &Album<EF2>) -> Self
+    where
+        EF1: ExtraFlag<Album<WithoutExtra>>,
+        EF2: ExtraFlag<Array<Track<WithoutExtra>>>,
+    {
+        Self {
+            id: track.id,
+            title: track.title.clone(),
+            version: track.version.clone(),
+            artist: track.performer.as_ref().map(|p| p.name.clone()),
+            album_id: album.id.clone(),
+            album_title: album.title.clone(),
+            album_artist: album.artist.name.clone(),
+            media_number: track.media_number,
+            track_number: track.track_number,
+            duration_secs: track.duration.as_secs(),
+            isrc: track.isrc.clone(),
+            release_date: track.release_date_original,
+            explicit: track.is_explicit(),
+            hires: track.hires,
+            streamable: track.streamable,
+        }
+    }
+}
+
+impl From<&Track<WithExtra>> for TrackDto {
+    fn from(track: &Track<WithExtra>) -> Self {
+        Self::new(track, &track.album)
+    }
+}
+
+/// An album along with its tracks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlbumDto {
+    pub id: String,
+    pub title: String,
+    pub version: Option<String>,
+    pub artist: String,
+    pub artist_id: i64,
+    pub genre: String,
+    pub label: String,
+    pub upc: String,
+    pub release_date: NaiveDate,
+    pub duration_secs: u64,
+    pub media_count: i64,
+    pub cover_url: String,
+    pub hires: bool,
+    pub streamable: bool,
+    pub tracks: Vec<TrackDto>,
+}
+
+impl From<&Album<WithExtra>> for AlbumDto {
+    fn from(album: &Album<WithExtra>) -> Self {
+        Self {
+            id: album.id.clone(),
+            title: album.title.clone(),
+            version: album.version.clone(),
+            artist: album.artist.name.clone(),
+            artist_id: album.artist.id,
+            genre: album.genre.name.clone(),
+            label: album.label.name.clone(),
+            upc: album.upc.clone(),
+            release_date: album.release_date_original,
+            duration_secs: album.duration.as_secs(),
+            media_count: album.media_count,
+            cover_url: album.image.large.clone(),
+            hires: album.hires,
+            streamable: album.streamable,
+            tracks: album
+                .tracks
+                .items
+                .iter()
+                .map(|track| TrackDto::new(track, album))
+                .collect(),
+        }
+    }
+}
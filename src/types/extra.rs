@@ -17,6 +17,13 @@ impl RootEntity for Track<WithExtra> {
     }
 }
 
+/// Qobuz always sends the album of a track, so this only skips deserializing it.
+impl RootEntity for Track<WithoutExtra> {
+    fn extra_arg<'b>() -> &'b str {
+        ""
+    }
+}
+
 impl RootEntity for Album<WithExtra> {
     fn extra_arg<'b>() -> &'b str {
         ""
@@ -43,16 +50,21 @@ impl ImplicitExtra for Album<WithoutExtra> {}
 impl ImplicitExtra for Artist<WithoutExtra> {}
 impl ImplicitExtra for Playlist<WithExtra> {}
 
-// TODO: Upgrade, downgrade methods
+// TODO: Upgrade methods
 // TODO: Change name ?
 pub trait ExtraFlag<T> {
     type Extra: DeserializeOwned + Serialize + Eq + Clone + Debug;
+
+    /// Whether `extra` stands for a missing extra, in which case it isn't serialized.
+    fn is_missing(_extra: &Self::Extra) -> bool {
+        false
+    }
 }
 
 // TODO: Rename, put in enum (enum probably won't work) ?
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct WithExtra;
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct WithoutExtra;
 
 impl<T> ExtraFlag<T> for WithExtra
@@ -63,9 +75,15 @@ where
 }
 impl<T> ExtraFlag<T> for WithoutExtra {
     type Extra = Empty;
+
+    fn is_missing(_extra: &Empty) -> bool {
+        true
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// The extra of an entity queried without it. Fields holding it are left out when serializing,
+/// and ignored when deserializing.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Empty;
 
 impl Serialize for Empty {
@@ -78,10 +96,13 @@ impl Serialize for Empty {
 }
 
 impl<'de> Deserialize<'de> for Empty {
-    fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
+        // Consume the value, if any, so that deserializers reading their input sequentially can
+        // go on with the following fields.
+        Option::<serde::de::IgnoredAny>::deserialize(deserializer)?;
         Ok(Self)
     }
 }
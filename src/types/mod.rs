@@ -1,14 +1,24 @@
 // Erroneous warning that is shown when using the same trait twice with different arguments
 #![allow(clippy::trait_duplication_in_bounds)]
 
+pub mod dto;
 pub mod extra;
 pub mod traits;
 
+use crate::quality::Quality;
 use chrono::{DateTime, Datelike, NaiveDate, Utc};
-use extra::{ExtraFlag, WithExtra, WithoutExtra};
+use extra::{Empty, ExtraFlag, WithExtra, WithoutExtra};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{fmt::Display, time::Duration};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    time::Duration,
+};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
 use url::Url;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -26,23 +36,63 @@ pub struct Playlist<EF: ExtraFlag<Array<Track<WithExtra>>>> {
     pub duration: Duration,
     pub genres: Vec<PlaylistGenre>,
     pub id: u64,
+    /// The covers of the first albums of the playlist, shown as a mosaic by Qobuz, as 50x50
+    /// thumbnails. See `cover_url` to get a single cover.
     pub images: Vec<Url>,
+    /// The same covers as `images`, as 150x150 images.
     pub images150: Vec<Url>,
+    /// The same covers as `images`, as 300x300 images.
     pub images300: Vec<Url>,
     pub is_collaborative: bool,
     pub is_featured: bool,
     pub updated_at: u64,
     pub users_count: u64,
+    #[serde(skip_serializing_if = "<EF as ExtraFlag<Array<Track<WithExtra>>>>::is_missing")]
     pub tracks: EF::Extra,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+impl<EF> Playlist<EF>
+where
+    EF: ExtraFlag<Array<Track<WithExtra>>>,
+{
+    /// A key for the playlist that is always present, made of its ID and a slug of its name,
+    /// e.g. `1234567-summer-hits`. See `Track::stable_slug`.
+    #[must_use]
+    pub fn stable_slug(&self) -> String {
+        stable_slug(&self.id, &self.name)
+    }
+
+    /// The URL of a single cover for the playlist in the given size, i.e. the first tile of its
+    /// mosaic, or `None` if the playlist has no images, e.g. because it is empty.
+    #[must_use]
+    pub fn cover_url(&self, size: PlaylistImageSize) -> Option<&Url> {
+        match size {
+            PlaylistImageSize::Thumbnail => &self.images,
+            PlaylistImageSize::Small => &self.images150,
+            PlaylistImageSize::Medium => &self.images300,
+        }
+        .first()
+    }
+}
+
+impl Playlist<WithExtra> {
+    /// The tracks of the playlist sorted by their position in it, since they may be returned out
+    /// of order. Tracks without a position come last, in the order they were returned.
+    #[must_use]
+    pub fn ordered_tracks(&self) -> Vec<&Track<WithExtra>> {
+        let mut tracks: Vec<_> = self.tracks.items.iter().collect();
+        tracks.sort_by_key(|track| (track.position.is_none(), track.position));
+        tracks
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Owner {
     pub id: i64,
     pub name: String,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Array<T> {
     pub items: Vec<T>,
     pub limit: i64,
@@ -50,7 +100,68 @@ pub struct Array<T> {
     pub total: i64,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+impl<T> Array<T> {
+    /// Whether there are more items after this page.
+    #[must_use]
+    pub fn has_more(&self) -> bool {
+        self.next_offset().is_some()
+    }
+
+    /// The offset at which the next page starts, or `None` if this is the last page.
+    #[must_use]
+    pub fn next_offset(&self) -> Option<i64> {
+        if self.items.is_empty() {
+            return None;
+        }
+        let next_offset = self
+            .offset
+            .saturating_add(i64::try_from(self.items.len()).unwrap_or(i64::MAX));
+        (next_offset < self.total).then_some(next_offset)
+    }
+
+    /// Add the items of the following page to this one, returning whether there were any.
+    pub(crate) fn append(&mut self, page: Self) -> bool {
+        if page.items.is_empty() {
+            return false;
+        }
+        self.items.extend(page.items);
+        self.limit = i64::try_from(self.items.len()).unwrap_or(i64::MAX);
+        true
+    }
+}
+
+/// The items of this page, so that `len` and `is_empty` are about this page rather than `total`.
+impl<T> Deref for Array<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> IntoIterator for Array<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Array<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+/// A track of the Qobuz catalog.
+///
+/// Tracks don't implement `Ord`: equality compares all of their fields, and no natural order
+/// would agree with it, so sort them with `cmp_by_position` instead.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Track<EF>
 where
     EF: ExtraFlag<Album<WithoutExtra>>,
@@ -64,6 +175,12 @@ where
     pub hires_streamable: bool,
     pub id: u64,
     pub isrc: String,
+    /// The maximum bit depth the track is available in, e.g. 24.
+    #[serde(default)]
+    pub maximum_bit_depth: Option<u8>,
+    /// The maximum sampling rate the track is available in.
+    #[serde(default)]
+    pub maximum_sampling_rate: Option<SamplingRate>,
     pub media_number: i64,
     pub parental_warning: bool,
     pub performer: Option<Performer>,
@@ -75,10 +192,21 @@ where
     pub release_date_original: NaiveDate,
     pub sampleable: bool,
     pub streamable: bool,
+    /// When the track becomes or became streamable, e.g. for pre-releases listed before their
+    /// release, if known.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub streamable_at: Option<DateTime<Utc>>,
+    /// When the track becomes or became purchasable, if known.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub purchasable_at: Option<DateTime<Utc>>,
     pub title: String,
     pub track_number: u64,
     pub version: Option<String>,
     pub work: Option<String>,
+    /// Loudness information computed by Qobuz, missing from some responses.
+    #[serde(default)]
+    pub audio_info: Option<AudioInfo>,
+    #[serde(skip_serializing_if = "<EF as ExtraFlag<Album<WithoutExtra>>>::is_missing")]
     pub album: EF::Extra,
 }
 
@@ -96,12 +224,256 @@ where
             self.performer
                 .clone()
                 .map_or("Various Artists".to_string(), |p| p.to_string()),
-            self.title,
+            self.full_title(),
             year
         )
     }
 }
 
+impl<EF> Track<EF>
+where
+    EF: ExtraFlag<Album<WithoutExtra>>,
+{
+    /// A key for the track that is always present and derived the same way for all items, made
+    /// of its ID and a slug of its title, e.g. `129342731-let-it-be`. Unlike the `slug` field,
+    /// which Qobuz only provides for some types, it can be used for any item as a file name,
+    /// cache key or URL fragment.
+    #[must_use]
+    pub fn stable_slug(&self) -> String {
+        stable_slug(&self.id, &self.title)
+    }
+
+    /// The title of the track including its work and version, formatted as
+    /// `work: title (version)`, e.g. `Symphony No. 5: I. Allegro con brio (Remastered 2009)`.
+    ///
+    /// The work and version are left out when missing or already part of the title.
+    #[must_use]
+    pub fn full_title(&self) -> String {
+        let (work, title, version) = self.title_parts();
+        let title = match work {
+            Some(work) => format!("{work}: {title}"),
+            None => title.to_string(),
+        };
+        match version {
+            Some(version) => format!("{title} ({version})"),
+            None => title,
+        }
+    }
+
+    /// The title of the track including its work and version like `full_title`, separated with
+    /// dashes as `work - title - version` to be used in file names.
+    #[must_use]
+    pub(crate) fn file_title(&self) -> String {
+        let (work, title, version) = self.title_parts();
+        [work, Some(title), version]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" - ")
+    }
+
+    /// The work, title and version of the track, leaving out the work and version when missing
+    /// or already part of the title.
+    fn title_parts(&self) -> (Option<&str>, &str, Option<&str>) {
+        let work = self
+            .work
+            .as_deref()
+            .filter(|work| !work.is_empty() && !self.title.starts_with(work));
+        let version = self.version.as_deref().filter(|version| {
+            !version.is_empty()
+                && !self.title.contains(version)
+                && !work.is_some_and(|work| work.contains(version))
+        });
+        (work, &self.title, version)
+    }
+
+    /// What the user can do with the track at `now` according to the catalog, e.g. to show a
+    /// badge. See `is_streamable_at` for how `streamable_at` is taken into account.
+    ///
+    /// This doesn't account for the user's subscription, see `Client::check_track_availability`.
+    #[must_use]
+    pub fn availability(&self, now: DateTime<Utc>) -> TrackAvailability {
+        if !self.displayable {
+            TrackAvailability::Unavailable
+        } else if let Some(streamable_at) =
+            self.streamable_at.filter(|&at| self.streamable && at > now)
+        {
+            TrackAvailability::NotYetStreamable(streamable_at)
+        } else if self.streamable {
+            TrackAvailability::FullStream {
+                hires: self.hires_streamable,
+            }
+        } else if self.purchasable || self.downloadable {
+            TrackAvailability::PurchaseRequired
+        } else if self.previewable || self.sampleable {
+            TrackAvailability::PreviewOnly
+        } else {
+            TrackAvailability::Unavailable
+        }
+    }
+
+    /// Whether the track has explicit content, i.e. its parental warning is set.
+    #[must_use]
+    pub const fn is_explicit(&self) -> bool {
+        self.parental_warning
+    }
+
+    /// Whether the track can be streamed at `now`: it must be streamable and, if it has a
+    /// streaming date, `now` must be after it.
+    #[must_use]
+    pub fn is_streamable_at(&self, now: DateTime<Utc>) -> bool {
+        match self.streamable_at {
+            Some(streamable_at) if streamable_at > now => false,
+            _ => self.streamable,
+        }
+    }
+
+    /// Compare tracks by their position in their album, i.e. their media and track numbers,
+    /// then by ID, e.g. to sort them with `sort_by`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use qobuz::types::{extra::WithExtra, Track};
+    /// fn sort(tracks: &mut [Track<WithExtra>]) {
+    ///     tracks.sort_by(Track::cmp_by_position);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn cmp_by_position(&self, other: &Self) -> Ordering {
+        (self.media_number, self.track_number, self.id).cmp(&(
+            other.media_number,
+            other.track_number,
+            other.id,
+        ))
+    }
+
+    /// Why the track can't be streamed at `now` according to its own information, without
+    /// querying its file, or `None` if it may be streamable. See `is_streamable_at`.
+    pub(crate) fn unavailability_reason(&self, now: DateTime<Utc>) -> Option<UnavailabilityReason> {
+        if let Some(streamable_at) = self.streamable_at.filter(|&at| at > now) {
+            return Some(UnavailabilityReason::NotYetStreamable(streamable_at));
+        }
+        (!self.streamable).then_some(UnavailabilityReason::NotStreamable {
+            purchasable: self.purchasable,
+        })
+    }
+
+    /// The credits of the track, parsed from `performers`, e.g. its composers, producers and
+    /// engineers. A person with several roles gets a credit for each of them.
+    ///
+    /// `performers` lists people separated by ` - `, each with their name followed by their
+    /// roles separated by commas, like `John Lennon, Composer, Lyricist - The Beatles, MainArtist`.
+    /// Since names may contain commas too, e.g. `Earth, Wind & Fire`, the roles are the trailing
+    /// ones known to be used by Qobuz, see `KNOWN_ROLES`. When none is known, everything after
+    /// the first comma is taken as roles.
+    #[must_use]
+    pub fn credits(&self) -> Vec<Credit> {
+        let Some(performers) = &self.performers else {
+            return Vec::new();
+        };
+        performers
+            .split(" - ")
+            .flat_map(|performer| {
+                let parts: Vec<&str> = performer.split(',').map(str::trim).collect();
+                let known_roles_start = parts
+                    .iter()
+                    .rposition(|part| !is_known_role(part))
+                    .map_or(0, |i| i + 1);
+                let roles_start = if known_roles_start == parts.len() {
+                    1
+                } else {
+                    known_roles_start.max(1)
+                };
+                let name = parts[..roles_start.min(parts.len())].join(", ");
+                parts
+                    .into_iter()
+                    .skip(roles_start)
+                    .filter(|role| !role.is_empty())
+                    .map(|role| Credit {
+                        role: role.to_string(),
+                        name: name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|credit| !credit.name.is_empty())
+            .collect()
+    }
+
+    /// Estimate the size in bytes of the file of the track in the given quality from its
+    /// duration, without any request.
+    ///
+    /// Lossy files use the bitrate of the quality, see `Quality::bitrate_hint`. Lossless files
+    /// use the bit depth and sampling rate the track is delivered in, assuming FLAC compresses
+    /// stereo PCM to `FLAC_COMPRESSION_RATIO` of its size, so the estimate may be off by a few
+    /// tens of percent depending on the music.
+    #[must_use]
+    pub fn estimated_size(&self, quality: &Quality) -> u64 {
+        let bits_per_second = quality.bitrate_hint().map_or_else(
+            || {
+                let (max_depth, max_rate) = match quality {
+                    Quality::HiRes192 => (24, 192.0),
+                    Quality::HiRes96 => (24, 96.0),
+                    Quality::Mp3 | Quality::Cd => (16, 44.1),
+                };
+                let depth = self.maximum_bit_depth.map_or(16, |d| d.min(max_depth));
+                let rate = self
+                    .maximum_sampling_rate
+                    .map_or(44.1, |r| r.0.min(max_rate));
+                f64::from(depth) * rate * 1000.0 * 2.0 * FLAC_COMPRESSION_RATIO
+            },
+            |kbps| f64::from(kbps) * 1000.0,
+        );
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let size = (bits_per_second / 8.0 * self.duration.as_secs() as f64).round() as u64;
+        size
+    }
+
+    /// A copy of the track without its album.
+    #[must_use]
+    pub fn without_extra(&self) -> Track<WithoutExtra> {
+        Track {
+            copyright: self.copyright.clone(),
+            displayable: self.displayable,
+            downloadable: self.downloadable,
+            duration: self.duration,
+            hires: self.hires,
+            hires_streamable: self.hires_streamable,
+            id: self.id,
+            isrc: self.isrc.clone(),
+            maximum_bit_depth: self.maximum_bit_depth,
+            maximum_sampling_rate: self.maximum_sampling_rate,
+            media_number: self.media_number,
+            parental_warning: self.parental_warning,
+            performer: self.performer.clone(),
+            performers: self.performers.clone(),
+            playlist_track_id: self.playlist_track_id,
+            position: self.position,
+            previewable: self.previewable,
+            purchasable: self.purchasable,
+            release_date_original: self.release_date_original,
+            sampleable: self.sampleable,
+            streamable: self.streamable,
+            streamable_at: self.streamable_at,
+            purchasable_at: self.purchasable_at,
+            title: self.title.clone(),
+            track_number: self.track_number,
+            version: self.version.clone(),
+            work: self.work.clone(),
+            audio_info: self.audio_info,
+            album: Empty,
+        }
+    }
+}
+
+/// An album of the Qobuz catalog.
+///
+/// Like tracks, albums don't implement `Ord` since no natural order would agree with their
+/// equality. Sort them with `cmp_by_release` instead.
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Album<EF>
 where
@@ -120,11 +492,33 @@ where
     pub media_count: i64,
     pub id: String,
     pub release_date_original: NaiveDate,
+    /// The date the album is or was made available for streaming, which may be later than its
+    /// original release date.
+    #[serde(default)]
+    pub release_date_stream: Option<NaiveDate>,
+    /// The date the album is or was made available for download.
+    #[serde(default)]
+    pub release_date_download: Option<NaiveDate>,
     pub sampleable: bool,
     pub streamable: bool,
+    /// When the album becomes or became streamable, e.g. for pre-releases listed before their
+    /// release, if known.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub streamable_at: Option<DateTime<Utc>>,
+    /// When the album becomes or became purchasable, if known.
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub purchasable_at: Option<DateTime<Utc>>,
     pub title: String,
+    /// The number of tracks of the album, on all its discs.
+    #[serde(default)]
+    pub tracks_count: Option<u64>,
+    /// The release type of the album as named by the API, e.g. `album` or `live`, if known.
+    /// See `ReleaseType::from_api_name`.
+    #[serde(default)]
+    pub release_type: Option<String>,
     pub upc: String,
     pub version: Option<String>,
+    #[serde(skip_serializing_if = "<EF as ExtraFlag<Array<Track<WithoutExtra>>>>::is_missing")]
     pub tracks: EF::Extra,
 }
 
@@ -143,6 +537,150 @@ where
     }
 }
 
+impl<EF> Album<EF>
+where
+    EF: ExtraFlag<Array<Track<WithoutExtra>>>,
+{
+    /// Compare albums by original release date, then by title and ID, e.g. to sort a
+    /// discography chronologically with `sort_by`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use qobuz::types::{extra::WithoutExtra, Album};
+    /// fn sort(albums: &mut [Album<WithoutExtra>]) {
+    ///     albums.sort_by(Album::cmp_by_release);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn cmp_by_release(&self, other: &Self) -> Ordering {
+        (&self.release_date_original, &self.title, &self.id).cmp(&(
+            &other.release_date_original,
+            &other.title,
+            &other.id,
+        ))
+    }
+
+    /// A key for the album that is always present, made of its ID and a slug of its title, e.g.
+    /// `trrcz9pvaaz6b-abbey-road`. See `Track::stable_slug`.
+    #[must_use]
+    pub fn stable_slug(&self) -> String {
+        stable_slug(&self.id, &self.title)
+    }
+
+    /// Whether the album is available for streaming on `today`, according to its streaming
+    /// release date or, if unknown, its original release date.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+    /// assert!(album.is_released(chrono::Utc::now().date_naive()));
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn is_released(&self, today: NaiveDate) -> bool {
+        self.release_date_stream
+            .unwrap_or(self.release_date_original)
+            <= today
+    }
+
+    /// Whether the album can be streamed at `now`: it must be streamable and, if it has a
+    /// streaming date, `now` must be after it.
+    #[must_use]
+    pub fn is_streamable_at(&self, now: DateTime<Utc>) -> bool {
+        match self.streamable_at {
+            Some(streamable_at) if streamable_at > now => false,
+            _ => self.streamable,
+        }
+    }
+
+    /// A copy of the album without its tracks.
+    #[must_use]
+    pub fn without_extra(&self) -> Album<WithoutExtra> {
+        Album {
+            artist: self.artist.clone(),
+            displayable: self.displayable,
+            downloadable: self.downloadable,
+            duration: self.duration,
+            genre: self.genre.clone(),
+            hires: self.hires,
+            hires_streamable: self.hires_streamable,
+            image: self.image.clone(),
+            label: self.label.clone(),
+            media_count: self.media_count,
+            id: self.id.clone(),
+            release_date_original: self.release_date_original,
+            release_date_stream: self.release_date_stream,
+            release_date_download: self.release_date_download,
+            sampleable: self.sampleable,
+            streamable: self.streamable,
+            streamable_at: self.streamable_at,
+            purchasable_at: self.purchasable_at,
+            title: self.title.clone(),
+            tracks_count: self.tracks_count,
+            release_type: self.release_type.clone(),
+            upc: self.upc.clone(),
+            version: self.version.clone(),
+            tracks: Empty,
+        }
+    }
+}
+
+impl Album<WithExtra> {
+    /// Whether any track of the album has explicit content.
+    #[must_use]
+    pub fn is_explicit(&self) -> bool {
+        self.tracks.items.iter().any(Track::is_explicit)
+    }
+
+    /// Group the tracks of the album by disc, keyed by media number, each disc's tracks being
+    /// sorted by track number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # use qobuz::{auth::Credentials, Client};
+    /// # let credentials = Credentials::from_env().unwrap();
+    /// # let client = Client::new(credentials).await.unwrap();
+    /// let album = client.get_album("trrcz9pvaaz6b").await.unwrap();
+    /// for (disc, tracks) in album.tracks_by_disc() {
+    ///     println!("Disc {disc}: {} tracks", tracks.len());
+    /// }
+    /// # })
+    /// ```
+    #[must_use]
+    pub fn tracks_by_disc(&self) -> BTreeMap<i64, Vec<&Track<WithoutExtra>>> {
+        let mut discs: BTreeMap<i64, Vec<&Track<WithoutExtra>>> = BTreeMap::new();
+        for track in &self.tracks.items {
+            discs.entry(track.media_number).or_default().push(track);
+        }
+        for tracks in discs.values_mut() {
+            tracks.sort_by_key(|track| track.track_number);
+        }
+        discs
+    }
+}
+
+impl<EF> Hash for Album<EF>
+where
+    EF: ExtraFlag<Array<Track<WithoutExtra>>>,
+{
+    // `Artist` can't be hashed because of its image, so only the ID is used.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+/// An artist of the Qobuz catalog.
+///
+/// Like tracks, artists don't implement `Ord` since no natural order would agree with their
+/// equality. Sort them with `cmp_by_name` instead.
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Artist<EF>
 where
@@ -153,7 +691,9 @@ where
     pub image: Value,
     pub name: String,
     pub slug: String,
+    #[serde(skip_serializing_if = "<EF as ExtraFlag<Array<Track<WithExtra>>>>::is_missing")]
     pub tracks: <EF as ExtraFlag<Array<Track<WithExtra>>>>::Extra,
+    #[serde(skip_serializing_if = "<EF as ExtraFlag<Array<Album<WithoutExtra>>>>::is_missing")]
     pub albums: <EF as ExtraFlag<Array<Album<WithoutExtra>>>>::Extra,
 }
 
@@ -166,7 +706,90 @@ where
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+impl<EF> Artist<EF>
+where
+    EF: ExtraFlag<Array<Track<WithExtra>>> + ExtraFlag<Array<Album<WithoutExtra>>>,
+{
+    /// Compare artists by name, then by ID, e.g. to sort them with `sort_by`.
+    #[must_use]
+    pub fn cmp_by_name(&self, other: &Self) -> Ordering {
+        (&self.name, self.id).cmp(&(&other.name, other.id))
+    }
+
+    /// A key for the artist that is always present, made of its ID and a slug of its name, e.g.
+    /// `26390-the-beatles`. See `Track::stable_slug`.
+    #[must_use]
+    pub fn stable_slug(&self) -> String {
+        stable_slug(&self.id, &self.name)
+    }
+}
+
+impl<EF> Hash for Artist<EF>
+where
+    EF: ExtraFlag<Array<Track<WithExtra>>> + ExtraFlag<Array<Album<WithoutExtra>>>,
+{
+    // The image is a `Value`, which can't be hashed, so only the ID is used.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+/// A sampling rate in kHz, e.g. 44.1 or 96.
+///
+/// The rate is compared and hashed bitwise so that it can be part of types implementing `Eq` and
+/// `Hash`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SamplingRate(pub f64);
+
+impl PartialEq for SamplingRate {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for SamplingRate {}
+
+impl Hash for SamplingRate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl Display for SamplingRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} kHz", self.0)
+    }
+}
+
+/// Loudness information of a track, as computed by Qobuz.
+///
+/// The values are compared and hashed bitwise, like `SamplingRate`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AudioInfo {
+    /// The ReplayGain track gain, in dB.
+    pub replaygain_track_gain: f64,
+    /// The ReplayGain track peak amplitude, where 1.0 is full scale.
+    pub replaygain_track_peak: f64,
+}
+
+impl PartialEq for AudioInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.replaygain_track_gain.to_bits() == other.replaygain_track_gain.to_bits()
+            && self.replaygain_track_peak.to_bits() == other.replaygain_track_peak.to_bits()
+    }
+}
+
+impl Eq for AudioInfo {}
+
+impl Hash for AudioInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.replaygain_track_gain.to_bits().hash(state);
+        self.replaygain_track_peak.to_bits().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Genre {
     pub color: String,
     pub id: u64,
@@ -175,14 +798,147 @@ pub struct Genre {
     pub slug: String,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+impl Genre {
+    /// The color of the genre as RGB components, or `None` if it isn't a hex color like
+    /// `#0070ef`.
+    #[must_use]
+    pub fn color_rgb(&self) -> Option<(u8, u8, u8)> {
+        parse_hex_color(&self.color)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Image {
     pub large: String,
     pub small: String,
     pub thumbnail: String,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+impl Image {
+    /// Get the URL of the image in the given size, derived from the URL of the large image, or
+    /// `None` if it isn't a valid cover URL.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use qobuz::types::{CoverSize, Image};
+    /// let image = Image {
+    ///     large: "https://static.qobuz.com/images/covers/6j/z6/trrcz9pvaaz6b_600.jpg".to_string(),
+    ///     small: "https://static.qobuz.com/images/covers/6j/z6/trrcz9pvaaz6b_230.jpg".to_string(),
+    ///     thumbnail: "https://static.qobuz.com/images/covers/6j/z6/trrcz9pvaaz6b_50.jpg".to_string(),
+    /// };
+    /// assert_eq!(
+    ///     image.url(CoverSize::Max).unwrap().as_str(),
+    ///     "https://static.qobuz.com/images/covers/6j/z6/trrcz9pvaaz6b_max.jpg"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn url(&self, size: CoverSize) -> Option<Url> {
+        // The size is the suffix of the file name, e.g. `<id>_600.jpg`.
+        let (prefix, _) = self.large.rsplit_once('_')?;
+        let (_, extension) = self.large.rsplit_once('.')?;
+        if prefix.ends_with('/') || extension.contains('/') {
+            return None;
+        }
+        Url::parse(&format!("{prefix}_{}.{extension}", size.suffix())).ok()
+    }
+
+    /// Get the URL of the image in the highest resolution, see `url`.
+    #[must_use]
+    pub fn max_url(&self) -> Option<Url> {
+        self.url(CoverSize::Max)
+    }
+}
+
+/// The size of FLAC files relative to the uncompressed audio, on average, used by
+/// `Track::estimated_size`.
+pub const FLAC_COMPRESSION_RATIO: f64 = 0.6;
+
+/// How `Client::estimate_download_size` computes the size of tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SizeEstimate {
+    /// Get the size of each file from Qobuz, which takes two requests per track.
+    Exact,
+    /// Estimate the size of each file from the duration of its track, see
+    /// `Track::estimated_size`. This doesn't make any request.
+    FromDuration,
+}
+
+/// The size of a playlist image, see `Playlist::cover_url`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlaylistImageSize {
+    /// 50x50 pixels
+    Thumbnail,
+    /// 150x150 pixels
+    Small,
+    /// 300x300 pixels
+    Medium,
+}
+
+/// The size of a cover image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoverSize {
+    /// 50x50 pixels
+    Thumbnail,
+    /// 230x230 pixels
+    Small,
+    /// 600x600 pixels
+    Large,
+    /// The highest resolution available as a JPEG image
+    Max,
+    /// The original image as provided to Qobuz
+    Original,
+}
+
+impl CoverSize {
+    const fn suffix(self) -> &'static str {
+        match self {
+            Self::Thumbnail => "50",
+            Self::Small => "230",
+            Self::Large => "600",
+            Self::Max => "max",
+            Self::Original => "org",
+        }
+    }
+}
+
+/// The type of a release of an artist, see `Client::get_artist_albums`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReleaseType {
+    Album,
+    /// EPs and singles, which Qobuz doesn't distinguish between.
+    EpSingle,
+    Live,
+    Compilation,
+}
+
+impl ReleaseType {
+    /// The name of the release type in the Qobuz API.
+    #[must_use]
+    pub const fn api_name(self) -> &'static str {
+        match self {
+            Self::Album => "album",
+            Self::EpSingle => "epSingle",
+            Self::Live => "live",
+            Self::Compilation => "compilation",
+        }
+    }
+
+    /// Get a release type from its name in the API, as found in `Album::release_type`. EPs and
+    /// singles may also be named `epmini` and `single`.
+    #[must_use]
+    pub fn from_api_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "album" => Some(Self::Album),
+            "epsingle" | "epmini" | "single" => Some(Self::EpSingle),
+            "live" => Some(Self::Live),
+            "compilation" => Some(Self::Compilation),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Label {
     pub albums_count: u64,
     pub id: u64,
@@ -191,13 +947,13 @@ pub struct Label {
     pub supplier_id: u64,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Composer {
     pub id: u64,
     pub name: String,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Performer {
     pub id: u64,
     pub name: String,
@@ -217,12 +973,337 @@ pub enum PlaylistGenre {
     },
 }
 
+impl PlaylistGenre {
+    /// The color of the genre as RGB components, see `Genre::color_rgb`. Genres given as a
+    /// plain string have no color.
+    #[must_use]
+    pub fn color_rgb(&self) -> Option<(u8, u8, u8)> {
+        match self {
+            Self::String(_) => None,
+            Self::Object { color, .. } => parse_hex_color(color),
+        }
+    }
+}
+
+/// Parse a color like `#0070ef` into its RGB components.
+fn parse_hex_color(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((component(0)?, component(2)?, component(4)?))
+}
+
+/// Join an ID and a slug of `title`, or only the ID if nothing is left of the title.
+fn stable_slug(id: &impl Display, title: &str) -> String {
+    let slug = slugify(title);
+    if slug.is_empty() {
+        id.to_string()
+    } else {
+        format!("{id}-{slug}")
+    }
+}
+
+/// Turn a title into lowercase ASCII words separated by dashes, e.g. `Café del Mar, Vol. 2` into
+/// `cafe-del-mar-vol-2`. Accents are removed, and characters that aren't letters or digits
+/// separate words, except for apostrophes, which are dropped so that `Don't` becomes `dont`.
+/// Letters without a decomposition are transliterated, e.g. `Straße` becomes `strasse`.
+fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    let mut pending_dash = false;
+    let chars = title
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c) && !matches!(c, '\'' | '’'));
+    for c in chars.flat_map(char::to_lowercase) {
+        let transliterated = match c {
+            'ß' => Some("ss"),
+            'æ' => Some("ae"),
+            'œ' => Some("oe"),
+            'ø' => Some("o"),
+            'ł' => Some("l"),
+            'đ' | 'ð' => Some("d"),
+            'þ' => Some("th"),
+            _ => None,
+        };
+        if c.is_ascii_alphanumeric() || transliterated.is_some() {
+            if pending_dash && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_dash = false;
+            match transliterated {
+                Some(ascii) => slug.push_str(ascii),
+                None => slug.push(c),
+            }
+        } else {
+            pending_dash = true;
+        }
+    }
+    slug
+}
+
+/// Roles found in the credits of tracks, compared ignoring case, spaces and dashes, so that e.g.
+/// `Mixing Engineer` and `MixingEngineer` are both known. Used to tell roles apart from names
+/// containing commas in `Track::credits`.
+const KNOWN_ROLES: &[&str] = &[
+    "Arranger",
+    "AssistantEngineer",
+    "AssociatedPerformer",
+    "Author",
+    "BackgroundVocalist",
+    "BackgroundVocals",
+    "BackingVocals",
+    "Bass",
+    "BassGuitar",
+    "Cello",
+    "Choir",
+    "Clarinet",
+    "CoProducer",
+    "Composer",
+    "ComposerLyricist",
+    "Conductor",
+    "Director",
+    "DoubleBass",
+    "Drums",
+    "Engineer",
+    "Ensemble",
+    "ExecutiveProducer",
+    "FeaturedArtist",
+    "Flute",
+    "Guitar",
+    "Harp",
+    "Keyboards",
+    "Label",
+    "LeadVocals",
+    "Lyricist",
+    "MainArtist",
+    "MasteringEngineer",
+    "Mixer",
+    "MixingEngineer",
+    "MusicPublisher",
+    "Orchestra",
+    "Orchestrator",
+    "Organ",
+    "Percussion",
+    "Performer",
+    "Piano",
+    "Producer",
+    "Programmer",
+    "Programming",
+    "Publisher",
+    "RecordingEngineer",
+    "Remixer",
+    "Saxophone",
+    "Soloist",
+    "StudioPersonnel",
+    "Synthesizer",
+    "Trombone",
+    "Trumpet",
+    "Viola",
+    "Violin",
+    "Vocals",
+    "Writer",
+];
+
+/// Whether `role` is one of `KNOWN_ROLES`.
+fn is_known_role(role: &str) -> bool {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    let role = normalize(role);
+    !role.is_empty() && KNOWN_ROLES.iter().any(|known| normalize(known) == role)
+}
+
+/// A person credited on a track with one of their roles, see `Track::credits`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Credit {
+    /// The role of the person, e.g. `Composer` or `MainArtist`.
+    pub role: String,
+    pub name: String,
+}
+
 impl Display for Performer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.name)
     }
 }
 
+/// What can be done with a track according to the catalog, see `Track::availability`.
+///
+/// This only relies on the information of the track, e.g. to show badges for many tracks at
+/// once. To know whether the user can actually stream a track in a given quality, which requires
+/// querying its file, see `Availability`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TrackAvailability {
+    /// The whole track can be streamed, in Hi-Res if `hires` is set.
+    FullStream { hires: bool },
+    /// The track isn't released yet and will be streamable at the given time.
+    NotYetStreamable(DateTime<Utc>),
+    /// The track can't be streamed but can be purchased.
+    PurchaseRequired,
+    /// Only a preview of the track can be played.
+    PreviewOnly,
+    /// The track can't be played nor purchased, e.g. because of region restrictions.
+    Unavailable,
+}
+
+/// Whether a track can be streamed in a given quality by the user, according to the file served
+/// by Qobuz, see `Client::check_track_availability`. See `TrackAvailability` for what the
+/// catalog says about a track without querying its file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Availability {
+    Available,
+    /// The track can only be streamed in a lower quality than the requested one.
+    Downgraded {
+        quality: Quality,
+        restrictions: Vec<Restriction>,
+    },
+    Unavailable(UnavailabilityReason),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UnavailabilityReason {
+    /// The track isn't streamable at all, e.g. because of region restrictions.
+    NotStreamable { purchasable: bool },
+    /// Only a sample of the track can be streamed.
+    Restricted(Vec<Restriction>),
+    /// The track isn't released yet and will be streamable at the given time.
+    NotYetStreamable(DateTime<Utc>),
+}
+
+impl Display for UnavailabilityReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotStreamable { purchasable: true } => {
+                write!(f, "track isn't streamable, but can be purchased")
+            }
+            Self::NotStreamable { purchasable: false } => write!(f, "track isn't streamable"),
+            Self::NotYetStreamable(streamable_at) => {
+                write!(
+                    f,
+                    "track will be available on {}",
+                    streamable_at.date_naive()
+                )
+            }
+            Self::Restricted(restrictions) => write!(
+                f,
+                "only a sample is available ({})",
+                restrictions
+                    .iter()
+                    .map(|r| r.code.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// A track file served by Qobuz.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TrackFile {
+    pub url: Url,
+    /// The quality the file is delivered in, which may be lower than the requested one.
+    #[serde(rename = "format_id")]
+    pub quality: Quality,
+    /// Whether only a sample of the track is served.
+    #[serde(default)]
+    pub sample: bool,
+    #[serde(default)]
+    pub restrictions: Vec<Restriction>,
+}
+
+impl TrackFile {
+    /// Whether the track can be streamed in `requested` quality given this file, i.e. whether
+    /// it is a sample or delivered in a lower quality.
+    pub(crate) fn availability(&self, requested: &Quality) -> Availability {
+        if self.sample {
+            Availability::Unavailable(UnavailabilityReason::Restricted(self.restrictions.clone()))
+        } else if &self.quality != requested {
+            Availability::Downgraded {
+                quality: self.quality.clone(),
+                restrictions: self.restrictions.clone(),
+            }
+        } else {
+            Availability::Available
+        }
+    }
+}
+
+/// A restriction returned by Qobuz when getting a track's file URL.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Restriction {
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UserInfo {
+    pub id: u64,
+    pub display_name: String,
+    pub country_code: String,
+    pub subscription: Option<Subscription>,
+    pub credential: Credential,
+}
+
+impl UserInfo {
+    /// Whether the account can stream Hi-Res tracks.
+    #[must_use]
+    pub const fn can_stream_hires(&self) -> bool {
+        self.credential.parameters.hires_streaming
+    }
+
+    /// The best quality the account is allowed to stream, if any.
+    #[must_use]
+    pub const fn max_quality(&self) -> Option<Quality> {
+        let parameters = &self.credential.parameters;
+        if parameters.hires_streaming {
+            Some(Quality::HiRes192)
+        } else if parameters.lossless_streaming {
+            Some(Quality::Cd)
+        } else if parameters.lossy_streaming {
+            Some(Quality::Mp3)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for UserInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_name)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub offer: String,
+    pub periodicity: String,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Credential {
+    pub label: Option<String>,
+    pub parameters: CredentialParameters,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CredentialParameters {
+    #[serde(default)]
+    pub lossy_streaming: bool,
+    #[serde(default)]
+    pub lossless_streaming: bool,
+    #[serde(default)]
+    pub hires_streaming: bool,
+    #[serde(default)]
+    pub hires_purchases_streaming: bool,
+    #[serde(default)]
+    pub offline_streaming: bool,
+}
+
 pub trait QobuzType {
     type EF;
     #[must_use]
@@ -321,3 +1402,341 @@ mod ser_duration_u64 {
         Ok(Duration::from_secs(u64::deserialize(deserializer)?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use crate::test_utils::fixture;
+
+    #[test]
+    fn test_without_extra_round_trip() {
+        let album: Album<WithExtra> = fixture("album");
+        let track = album.tracks.items[0].clone();
+        let json = serde_json::to_string(&track).unwrap();
+        assert!(!json.contains("\"album\""));
+        assert_eq!(
+            serde_json::from_str::<Track<WithoutExtra>>(&json).unwrap(),
+            track
+        );
+
+        // Extras present in the input are skipped.
+        let track: Track<WithExtra> = fixture("track");
+        let json = serde_json::to_string(&track).unwrap();
+        let without_album: Track<WithoutExtra> = serde_json::from_str(&json).unwrap();
+        assert_eq!(without_album.id, track.id);
+        assert_eq!(without_album.title, track.title);
+    }
+
+    #[test]
+    fn test_track_availability() {
+        let mut track: Track<WithExtra> = fixture("track");
+        let now = Utc::now();
+        assert_eq!(
+            track.availability(now),
+            TrackAvailability::FullStream { hires: true }
+        );
+        let release = now + chrono::Duration::days(1);
+        track.streamable_at = Some(release);
+        assert_eq!(
+            track.availability(now),
+            TrackAvailability::NotYetStreamable(release)
+        );
+        assert_eq!(
+            track.availability(release),
+            TrackAvailability::FullStream { hires: true }
+        );
+        track.streamable_at = None;
+        track.hires_streamable = false;
+        assert_eq!(
+            track.availability(now),
+            TrackAvailability::FullStream { hires: false }
+        );
+        track.streamable = false;
+        assert_eq!(track.availability(now), TrackAvailability::PurchaseRequired);
+        track.purchasable = false;
+        track.downloadable = false;
+        assert_eq!(track.availability(now), TrackAvailability::PreviewOnly);
+        track.previewable = false;
+        track.sampleable = false;
+        assert_eq!(track.availability(now), TrackAvailability::Unavailable);
+        track.streamable = true;
+        track.displayable = false;
+        assert_eq!(track.availability(now), TrackAvailability::Unavailable);
+    }
+
+    #[test]
+    fn test_audio_info() {
+        let mut json: Value = fixture("track");
+        let track: Track<WithExtra> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(track.audio_info, None);
+
+        json["audio_info"] = serde_json::json!({
+            "replaygain_track_gain": -9.77,
+            "replaygain_track_peak": 0.999_969,
+        });
+        let track: Track<WithExtra> = serde_json::from_value(json).unwrap();
+        let audio_info = track.audio_info.unwrap();
+        assert!((audio_info.replaygain_track_gain + 9.77).abs() < f64::EPSILON);
+        assert!((audio_info.replaygain_track_peak - 0.999_969).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_genre_color_rgb() {
+        let album: Album<WithExtra> = fixture("album");
+        let mut genre = album.genre;
+        assert_eq!(genre.color_rgb(), Some((0x00, 0x70, 0xef)));
+        for color in [
+            "", "#", "0070ef", "#0070e", "#0070efa", "#00g0ef", "#+1+1+1",
+        ] {
+            genre.color = color.to_string();
+            assert_eq!(genre.color_rgb(), None, "{color}");
+        }
+        assert_eq!(PlaylistGenre::String("Pop".to_string()).color_rgb(), None);
+    }
+
+    #[test]
+    fn test_track_credits() {
+        let mut track: Track<WithExtra> = fixture("track");
+        let credit = |role: &str, name: &str| Credit {
+            role: role.to_string(),
+            name: name.to_string(),
+        };
+        assert_eq!(
+            track.credits(),
+            [
+                credit("MainArtist", "Mock Artist"),
+                credit("Composer", "Mock Composer")
+            ]
+        );
+
+        track.performers =
+            Some("John Lennon, Composer, Lyricist - George Martin, Producer - Nobody".to_string());
+        assert_eq!(
+            track.credits(),
+            [
+                credit("Composer", "John Lennon"),
+                credit("Lyricist", "John Lennon"),
+                credit("Producer", "George Martin"),
+            ]
+        );
+
+        track.performers = Some(
+            "Earth, Wind & Fire, MainArtist - Tyler, The Creator, Composer, Lyricist - \
+             Jane Doe, Theremin - Bob Smith, Mixing Engineer"
+                .to_string(),
+        );
+        assert_eq!(
+            track.credits(),
+            [
+                credit("MainArtist", "Earth, Wind & Fire"),
+                credit("Composer", "Tyler, The Creator"),
+                credit("Lyricist", "Tyler, The Creator"),
+                credit("Theremin", "Jane Doe"),
+                credit("Mixing Engineer", "Bob Smith"),
+            ]
+        );
+
+        track.performers = None;
+        assert!(track.credits().is_empty());
+    }
+
+    #[test]
+    fn test_playlist_ordered_tracks() {
+        let mut playlist: Playlist<WithExtra> = fixture("playlist");
+        let track = playlist.tracks.items[0].clone();
+        playlist.tracks.items = [(1, Some(3)), (2, None), (3, Some(1)), (4, Some(2))]
+            .into_iter()
+            .map(|(id, position)| Track {
+                id,
+                position,
+                ..track.clone()
+            })
+            .collect();
+        let ids: Vec<_> = playlist.ordered_tracks().iter().map(|t| t.id).collect();
+        assert_eq!(ids, [3, 4, 1, 2]);
+    }
+
+    #[test]
+    fn test_track_estimated_size() {
+        let mut track: Track<WithExtra> = fixture("track");
+        track.duration = Duration::from_secs(100);
+        assert_eq!(track.estimated_size(&Quality::Mp3), 4_000_000);
+        track.maximum_bit_depth = Some(24);
+        track.maximum_sampling_rate = Some(SamplingRate(96.0));
+        let cd = track.estimated_size(&Quality::Cd);
+        assert_eq!(cd, 10_584_000);
+        let hires96 = track.estimated_size(&Quality::HiRes96);
+        assert!(hires96 > cd);
+        // The track isn't available in a higher sampling rate.
+        assert_eq!(track.estimated_size(&Quality::HiRes192), hires96);
+    }
+
+    #[test]
+    fn test_streamable_at() {
+        let mut json: Value = fixture("track");
+        let track: Track<WithExtra> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(track.streamable_at, None);
+        assert!(track.is_streamable_at(Utc::now()));
+
+        let release = DateTime::from_timestamp(1_900_000_000, 0).unwrap();
+        json["streamable"] = false.into();
+        json["streamable_at"] = release.timestamp().into();
+        json["purchasable_at"] = Value::Null;
+        let mut track: Track<WithExtra> = serde_json::from_value(json).unwrap();
+        assert_eq!(track.streamable_at, Some(release));
+        assert_eq!(track.purchasable_at, None);
+        assert!(!track.is_streamable_at(release - chrono::Duration::days(1)));
+        track.streamable = true;
+        assert!(!track.is_streamable_at(release - chrono::Duration::days(1)));
+        assert!(track.is_streamable_at(release));
+        assert_eq!(
+            track.unavailability_reason(release - chrono::Duration::days(1)),
+            Some(UnavailabilityReason::NotYetStreamable(release))
+        );
+        assert_eq!(track.unavailability_reason(release), None);
+        track.streamable = false;
+        assert_eq!(
+            track.unavailability_reason(release),
+            Some(UnavailabilityReason::NotStreamable {
+                purchasable: track.purchasable
+            })
+        );
+    }
+
+    #[test]
+    fn test_track_file_availability() {
+        let restrictions = vec![Restriction {
+            code: "FormatRestrictedByFormatAvailability".to_string(),
+        }];
+        let mut file = TrackFile {
+            url: Url::parse("https://streaming.qobuz.com/file?etsp=1700000000").unwrap(),
+            quality: Quality::Cd,
+            sample: false,
+            restrictions: restrictions.clone(),
+        };
+        assert_eq!(file.availability(&Quality::Cd), Availability::Available);
+        assert_eq!(
+            file.availability(&Quality::HiRes192),
+            Availability::Downgraded {
+                quality: Quality::Cd,
+                restrictions: restrictions.clone()
+            }
+        );
+        file.sample = true;
+        assert_eq!(
+            file.availability(&Quality::Cd),
+            Availability::Unavailable(UnavailabilityReason::Restricted(restrictions))
+        );
+    }
+
+    #[test]
+    fn test_playlist_cover_url() {
+        let mut playlist: Playlist<WithExtra> = fixture("playlist");
+        assert_eq!(playlist.cover_url(PlaylistImageSize::Medium), None);
+        let url = |size: &str, i: u8| {
+            Url::parse(&format!(
+                "https://static.qobuz.com/images/covers/{i}_{size}.jpg"
+            ))
+            .unwrap()
+        };
+        playlist.images = vec![url("50", 1), url("50", 2)];
+        playlist.images150 = vec![url("150", 1), url("150", 2)];
+        playlist.images300 = vec![url("300", 1), url("300", 2)];
+        assert_eq!(
+            playlist.cover_url(PlaylistImageSize::Thumbnail),
+            Some(&url("50", 1))
+        );
+        assert_eq!(
+            playlist.cover_url(PlaylistImageSize::Small),
+            Some(&url("150", 1))
+        );
+        assert_eq!(
+            playlist.cover_url(PlaylistImageSize::Medium),
+            Some(&url("300", 1))
+        );
+    }
+
+    #[test]
+    fn test_slugify() {
+        let cases = [
+            ("Let It Be", "let-it-be"),
+            ("Café del Mar, Vol. 2", "cafe-del-mar-vol-2"),
+            ("Don't Stop Me Now", "dont-stop-me-now"),
+            ("Björk – Jóga (Remastered)", "bjork-joga-remastered"),
+            ("  ...Baby One More Time!  ", "baby-one-more-time"),
+            ("AC/DC: Live", "ac-dc-live"),
+            ("Ágætis byrjun", "agaetis-byrjun"),
+            ("Straße", "strasse"),
+            ("Søren Łukasz", "soren-lukasz"),
+            ("東京", ""),
+        ];
+        for (title, slug) in cases {
+            assert_eq!(slugify(title), slug, "{title}");
+        }
+    }
+
+    #[test]
+    fn test_stable_slug() {
+        let mut track: Track<WithExtra> = fixture("track");
+        track.title = "Señorita (feat. Camila Cabello)".to_string();
+        assert_eq!(
+            track.stable_slug(),
+            format!("{}-senorita-feat-camila-cabello", track.id)
+        );
+        track.title = "東京".to_string();
+        assert_eq!(track.stable_slug(), track.id.to_string());
+        let album: Album<WithExtra> = fixture("album");
+        assert!(album.stable_slug().starts_with(&format!("{}-", album.id)));
+    }
+
+    #[test]
+    fn test_array_iter() {
+        let album: Album<WithExtra> = fixture("album");
+        let tracks = album.tracks;
+        assert_eq!(tracks.len(), tracks.items.len());
+        assert!(!tracks.is_empty());
+        let borrowed: Vec<u64> = (&tracks).into_iter().map(|track| track.id).collect();
+        assert_eq!(
+            borrowed,
+            tracks.iter().map(|track| track.id).collect::<Vec<_>>()
+        );
+        let owned: Vec<u64> = tracks.into_iter().map(|track| track.id).collect();
+        assert_eq!(owned, borrowed);
+    }
+
+    #[test]
+    fn test_cmp_by_position() {
+        let album: Album<WithExtra> = fixture("album");
+        let mut tracks = album.tracks.items.clone();
+        tracks.reverse();
+        tracks.sort_by(Track::cmp_by_position);
+        assert_eq!(tracks, album.tracks.items);
+    }
+
+    #[test]
+    fn test_full_title() {
+        let mut track: Track<WithExtra> = fixture("track");
+        track.title = "I. Allegro con brio".to_string();
+        track.work = Some("Symphony No. 5".to_string());
+        track.version = Some("Remastered 2009".to_string());
+        assert_eq!(
+            track.full_title(),
+            "Symphony No. 5: I. Allegro con brio (Remastered 2009)"
+        );
+        assert_eq!(
+            track.file_title(),
+            "Symphony No. 5 - I. Allegro con brio - Remastered 2009"
+        );
+
+        track.title = "Symphony No. 5: I. Allegro con brio (Remastered 2009)".to_string();
+        assert_eq!(track.full_title(), track.title);
+        assert_eq!(track.file_title(), track.title);
+
+        track.title = "Let It Be".to_string();
+        track.work = None;
+        track.version = None;
+        assert_eq!(track.full_title(), "Let It Be");
+        assert_eq!(track.file_title(), "Let It Be");
+    }
+}